@@ -2,14 +2,19 @@ use std::{
   ffi::CStr,
   mem,
   ops::Drop,
+  os::raw::c_void,
   ptr::{self, NonNull},
   slice,
 };
 
 use crate::{
-  avcodec::{AVCodecID, AVCodecParameters, AVPacket},
+  avcodec::{
+    profile_list, AVCodecDescriptor, AVCodecDescriptorRef, AVCodecID,
+    AVCodecParameters, AVPacket,
+  },
   avutil::{
-    AVChannelLayoutRef, AVDictionary, AVFrame, AVPixelFormat, AVRational,
+    capture_log, AVChannelLayoutRef, AVDictionary, AVFrame, AVMediaType,
+    AVOptions, AVPixelFormat, AVRational,
   },
   error::{Result, RsmpegError},
   ffi,
@@ -56,6 +61,54 @@ impl AVCodec {
   pub fn long_name(&self) -> &CStr {
     unsafe { CStr::from_ptr(self.long_name) }
   }
+
+  /// Create an iterator on all the encoders and decoders of the linked
+  /// FFmpeg.
+  pub fn iterate() -> AVCodecIter {
+    AVCodecIter {
+      opaque: ptr::null_mut(),
+    }
+  }
+
+  /// Get the media type the codec handles.
+  pub fn media_type(&self) -> AVMediaType {
+    AVMediaType(self.type_)
+  }
+
+  /// Is this codec an encoder.
+  pub fn is_encoder(&self) -> bool {
+    unsafe { ffi::av_codec_is_encoder(self.as_ptr()) != 0 }
+  }
+
+  /// Is this codec a decoder.
+  pub fn is_decoder(&self) -> bool {
+    unsafe { ffi::av_codec_is_decoder(self.as_ptr()) != 0 }
+  }
+
+  /// Get the capabilities of the codec.
+  pub fn capabilities(&self) -> CodecCapabilities {
+    CodecCapabilities::from_bits_retain(self.capabilities)
+  }
+
+  /// Get the descriptor of the codec this implementation is for, which
+  /// holds the properties shared by all its encoders and decoders.
+  pub fn codec_descriptor(&self) -> Option<AVCodecDescriptorRef<'static>> {
+    AVCodecDescriptor::get(self.id)
+  }
+}
+
+/// Iterator of the registered codecs, created by [`AVCodec::iterate`].
+pub struct AVCodecIter {
+  opaque: *mut c_void,
+}
+
+impl std::iter::Iterator for AVCodecIter {
+  type Item = AVCodecRef<'static>;
+  fn next(&mut self) -> Option<Self::Item> {
+    unsafe { ffi::av_codec_iterate(&mut self.opaque) }
+      .upgrade()
+      .map(|x| unsafe { AVCodecRef::from_raw(x) })
+  }
 }
 
 impl<'codec> AVCodec {
@@ -110,10 +163,73 @@ impl<'codec> AVCodec {
     Self::build_array(self.sample_fmts, -1)
   }
 
+  #[deprecated = "Use `ch_layouts` instead, FFmpeg deprecated the channel masks."]
   pub fn channel_layouts(&'codec self) -> Option<&'codec [u64]> {
     // terminates with -1
     Self::build_array(self.channel_layouts, 0)
   }
+
+  /// Return supported channel layouts of this [`AVCodec`].
+  pub fn ch_layouts(&'codec self) -> Option<Vec<AVChannelLayoutRef<'codec>>> {
+    let mut ptr = self.ch_layouts.upgrade()?;
+    let mut ch_layouts = vec![];
+    // terminates with a zeroed layout
+    while unsafe { ptr.as_ref() }.nb_channels != 0 {
+      ch_layouts.push(unsafe { AVChannelLayoutRef::from_raw(ptr) });
+      ptr = unsafe { NonNull::new_unchecked(ptr.as_ptr().add(1)) };
+    }
+    Some(ch_layouts)
+  }
+
+  /// Return the `(id, name)` of the profiles this [`AVCodec`] supports, `None`
+  /// if unknown.
+  pub fn profiles(&'codec self) -> Option<Vec<(i32, &'codec CStr)>> {
+    profile_list(self.profiles)
+  }
+}
+
+bitflags::bitflags! {
+  /// Codec capabilities, a combination of `AV_CODEC_CAP_*` flags.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct CodecCapabilities: i32 {
+    const DRAW_HORIZ_BAND = ffi::AV_CODEC_CAP_DRAW_HORIZ_BAND as i32;
+    /// The codec uses `get_buffer` for allocating buffers.
+    const DR1 = ffi::AV_CODEC_CAP_DR1 as i32;
+    /// The codec has a delay, it needs to be flushed with a `None`
+    /// frame or packet at the end.
+    const DELAY = ffi::AV_CODEC_CAP_DELAY as i32;
+    /// The encoder accepts a smaller last audio frame.
+    const SMALL_LAST_FRAME = ffi::AV_CODEC_CAP_SMALL_LAST_FRAME as i32;
+    const SUBFRAMES = ffi::AV_CODEC_CAP_SUBFRAMES as i32;
+    /// The codec is experimental and needs a strict compliance setting.
+    const EXPERIMENTAL = ffi::AV_CODEC_CAP_EXPERIMENTAL as i32;
+    const CHANNEL_CONF = ffi::AV_CODEC_CAP_CHANNEL_CONF as i32;
+    /// The codec supports frame-level multithreading.
+    const FRAME_THREADS = ffi::AV_CODEC_CAP_FRAME_THREADS as i32;
+    /// The codec supports slice-based multithreading.
+    const SLICE_THREADS = ffi::AV_CODEC_CAP_SLICE_THREADS as i32;
+    const PARAM_CHANGE = ffi::AV_CODEC_CAP_PARAM_CHANGE as i32;
+    /// The codec supports multithreading through another method.
+    const OTHER_THREADS = ffi::AV_CODEC_CAP_OTHER_THREADS as i32;
+    /// The audio encoder accepts frames of any size.
+    const VARIABLE_FRAME_SIZE = ffi::AV_CODEC_CAP_VARIABLE_FRAME_SIZE as i32;
+    const AVOID_PROBING = ffi::AV_CODEC_CAP_AVOID_PROBING as i32;
+    /// The codec is backed by a hardware implementation.
+    const HARDWARE = ffi::AV_CODEC_CAP_HARDWARE as i32;
+    /// The codec may be backed by a hardware implementation.
+    const HYBRID = ffi::AV_CODEC_CAP_HYBRID as i32;
+    /// The encoder copies `opaque` of the frames to the packets, even with
+    /// reordering.
+    const ENCODER_REORDERED_OPAQUE =
+      ffi::AV_CODEC_CAP_ENCODER_REORDERED_OPAQUE as i32;
+    /// The encoder can be flushed with `avcodec_flush_buffers`.
+    const ENCODER_FLUSH = ffi::AV_CODEC_CAP_ENCODER_FLUSH as i32;
+    /// The encoder can output reconstructed frames.
+    const ENCODER_RECON_FRAME = ffi::AV_CODEC_CAP_ENCODER_RECON_FRAME as i32;
+
+    // Keep unknown flags of newer FFmpeg versions.
+    const _ = !0;
+  }
 }
 
 impl Drop for AVCodec {
@@ -164,6 +280,13 @@ impl AVCodecContext {
   pub fn open(
     &mut self,
     dict: Option<AVDictionary>,
+  ) -> Result<Option<AVDictionary>> {
+    capture_log(|| self.open_impl(dict))
+  }
+
+  fn open_impl(
+    &mut self,
+    dict: Option<AVDictionary>,
   ) -> Result<Option<AVDictionary>> {
     if let Some(mut dict) = dict {
       let dict_ptr = {
@@ -272,6 +395,16 @@ impl AVCodecContext {
     }
   }
 
+  /// Reset the internal codec state / flush internal buffers. Should be
+  /// called e.g. when seeking or when switching to a different stream.
+  ///
+  /// For decoders, this drops any pending frames, so the next
+  /// [`Self::receive_frame()`] only returns frames decoded from packets sent
+  /// after flushing.
+  pub fn flush_buffers(&mut self) {
+    unsafe { ffi::avcodec_flush_buffers(self.as_mut_ptr()) }
+  }
+
   /// Decode a subtitle message.
   ///
   /// Some decoders (those marked with `AV_CODEC_CAP_DELAY`) have a delay
@@ -396,6 +529,12 @@ impl<'ctx> AVCodecContext {
   }
 }
 
+unsafe impl AVOptions for AVCodecContext {
+  fn as_opt_ptr(&self) -> *mut c_void {
+    self.as_ptr() as *mut _
+  }
+}
+
 impl Drop for AVCodecContext {
   fn drop(&mut self) {
     // A pointer holder
@@ -442,3 +581,43 @@ impl Drop for AVSubtitle {
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cstr::cstr;
+
+  #[test]
+  fn test_codec_iterate() {
+    let codecs = AVCodec::iterate().collect::<Vec<_>>();
+    let h264 = codecs
+      .iter()
+      .find(|x| x.name() == cstr!("h264") && x.is_decoder())
+      .unwrap();
+    assert!(!h264.is_encoder());
+    assert_eq!(h264.id, ffi::AV_CODEC_ID_H264);
+    assert!(h264.media_type().is_video());
+    assert!(codecs.iter().any(|x| x.is_encoder()));
+  }
+
+  #[test]
+  fn test_codec_capabilities() {
+    let decoder = AVCodec::find_decoder(ffi::AV_CODEC_ID_H264).unwrap();
+    let capabilities = decoder.capabilities();
+    assert!(capabilities.contains(CodecCapabilities::DELAY));
+    assert!(capabilities.contains(CodecCapabilities::FRAME_THREADS));
+    assert!(decoder
+      .profiles()
+      .unwrap()
+      .contains(&(ffi::AV_PROFILE_H264_MAIN as i32, cstr!("Main"))));
+    let descriptor = decoder.codec_descriptor().unwrap();
+    assert_eq!(descriptor.name(), cstr!("h264"));
+
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_AAC).unwrap();
+    assert!(encoder
+      .capabilities()
+      .contains(CodecCapabilities::SMALL_LAST_FRAME));
+    let ch_layouts = encoder.ch_layouts().unwrap();
+    assert!(ch_layouts.iter().any(|x| x.nb_channels == 2));
+  }
+}
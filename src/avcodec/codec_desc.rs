@@ -0,0 +1,135 @@
+use crate::{
+  avcodec::AVCodecID, avutil::AVMediaType, ffi, shared::PointerUpgrade,
+};
+use std::{ffi::CStr, ptr::NonNull};
+
+wrap_ref!(AVCodecDescriptor: ffi::AVCodecDescriptor);
+
+impl AVCodecDescriptor {
+  /// Get the descriptor of the codec with the given [`AVCodecID`].
+  pub fn get(id: AVCodecID) -> Option<AVCodecDescriptorRef<'static>> {
+    unsafe { ffi::avcodec_descriptor_get(id) }
+      .upgrade()
+      .map(|x| unsafe { AVCodecDescriptorRef::from_raw(x) })
+  }
+
+  /// Get the descriptor of the codec with the given name, e.g. `h264`.
+  pub fn get_by_name(name: &CStr) -> Option<AVCodecDescriptorRef<'static>> {
+    unsafe { ffi::avcodec_descriptor_get_by_name(name.as_ptr()) }
+      .upgrade()
+      .map(|x| unsafe { AVCodecDescriptorRef::from_raw(x) })
+  }
+
+  /// Get the name of the codec, e.g. `h264`.
+  pub fn name(&self) -> &CStr {
+    unsafe { CStr::from_ptr(self.name) }
+  }
+
+  /// Get the descriptive name of the codec.
+  pub fn long_name(&self) -> Option<&CStr> {
+    self
+      .long_name
+      .upgrade()
+      .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+  }
+
+  /// Get the media type of the codec.
+  pub fn media_type(&self) -> AVMediaType {
+    AVMediaType(self.type_)
+  }
+
+  /// Get the properties of the codec.
+  pub fn props(&self) -> CodecProps {
+    CodecProps::from_bits_retain(self.props)
+  }
+
+  /// Get the MIME types of the codec, the preferred one first.
+  pub fn mime_types(&self) -> Vec<&CStr> {
+    let mut mime_types = vec![];
+    if let Some(mut ptr) = self.mime_types.upgrade() {
+      // Terminated with NULL.
+      while let Some(mime_type) = unsafe { *ptr.as_ptr() }.upgrade() {
+        mime_types.push(unsafe { CStr::from_ptr(mime_type.as_ptr()) });
+        ptr = unsafe { NonNull::new_unchecked(ptr.as_ptr().add(1)) };
+      }
+    }
+    mime_types
+  }
+
+  /// Get the `(id, name)` of the profiles recognized for the codec, `None`
+  /// if unknown.
+  pub fn profiles(&self) -> Option<Vec<(i32, &CStr)>> {
+    profile_list(self.profiles)
+  }
+}
+
+/// Collect a profile array terminated with `AV_PROFILE_UNKNOWN`.
+pub(crate) fn profile_list<'a>(
+  ptr: *const ffi::AVProfile,
+) -> Option<Vec<(i32, &'a CStr)>> {
+  let mut ptr = ptr.upgrade()?;
+  let mut profiles = vec![];
+  loop {
+    let profile = unsafe { ptr.as_ref() };
+    if profile.profile == ffi::AV_PROFILE_UNKNOWN {
+      return Some(profiles);
+    }
+    if let Some(name) = profile.name.upgrade() {
+      profiles
+        .push((profile.profile, unsafe { CStr::from_ptr(name.as_ptr()) }));
+    }
+    ptr = unsafe { NonNull::new_unchecked(ptr.as_ptr().add(1)) };
+  }
+}
+
+bitflags::bitflags! {
+  /// Codec properties, a combination of `AV_CODEC_PROP_*` flags.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct CodecProps: i32 {
+    /// Every frame is a keyframe.
+    const INTRA_ONLY = ffi::AV_CODEC_PROP_INTRA_ONLY as i32;
+    /// The codec supports lossy compression, it may support lossless too.
+    const LOSSY = ffi::AV_CODEC_PROP_LOSSY as i32;
+    /// The codec supports lossless compression, it may support lossy too.
+    const LOSSLESS = ffi::AV_CODEC_PROP_LOSSLESS as i32;
+    /// The codec supports frame reordering, i.e. B-frames.
+    const REORDER = ffi::AV_CODEC_PROP_REORDER as i32;
+    /// Video codec supporting separate coding of fields.
+    const FIELDS = ffi::AV_CODEC_PROP_FIELDS as i32;
+    /// Subtitle codec based on bitmaps.
+    const BITMAP_SUB = ffi::AV_CODEC_PROP_BITMAP_SUB as i32;
+    /// Subtitle codec based on text.
+    const TEXT_SUB = ffi::AV_CODEC_PROP_TEXT_SUB as i32;
+
+    // Keep unknown flags of newer FFmpeg versions.
+    const _ = !0;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use cstr::cstr;
+
+  #[test]
+  fn test_codec_descriptor() {
+    let h264 = AVCodecDescriptor::get(ffi::AV_CODEC_ID_H264).unwrap();
+    assert_eq!(h264.name(), cstr!("h264"));
+    assert!(h264.media_type().is_video());
+    assert!(h264
+      .props()
+      .contains(CodecProps::LOSSY | CodecProps::REORDER));
+    assert!(h264
+      .profiles()
+      .unwrap()
+      .contains(&(ffi::AV_PROFILE_H264_HIGH as i32, cstr!("High"))));
+
+    let png = AVCodecDescriptor::get_by_name(cstr!("png")).unwrap();
+    assert_eq!(png.id, ffi::AV_CODEC_ID_PNG);
+    assert!(png
+      .props()
+      .contains(CodecProps::INTRA_ONLY | CodecProps::LOSSLESS));
+    assert_eq!(png.mime_types(), [cstr!("image/png")]);
+    assert!(png.profiles().is_none());
+  }
+}
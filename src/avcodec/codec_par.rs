@@ -10,9 +10,18 @@ use std::{
   fmt,
   ops::{Deref, Drop},
   ptr::NonNull,
+  slice,
 };
 
 wrap_ref_mut!(AVCodecParameters: ffi::AVCodecParameters);
+settable!(AVCodecParameters {
+  codec_type: ffi::AVMediaType,
+  codec_id: ffi::AVCodecID,
+  codec_tag: u32,
+  format: i32,
+  width: i32,
+  height: i32,
+});
 
 impl AVCodecParameters {
   /// The constructor.
@@ -58,6 +67,48 @@ impl AVCodecParameters {
     let inner = NonNull::new(&self.ch_layout as *const _ as *mut _).unwrap();
     unsafe { AVChannelLayoutRef::from_raw(inner) }
   }
+
+  /// Get the extradata, e.g. the codec private data of the container.
+  pub fn extradata(&self) -> &[u8] {
+    if self.extradata.is_null() || self.extradata_size <= 0 {
+      &[]
+    } else {
+      unsafe {
+        slice::from_raw_parts(self.extradata, self.extradata_size as usize)
+      }
+    }
+  }
+
+  /// Get the names of the fields which differ between `self` and `other`
+  /// in a way that packets of one stream can't be stream copied into a
+  /// stream described by the other, i.e. codec type and id, dimensions,
+  /// extradata, sample rate and channel layout. Empty if they're
+  /// compatible.
+  pub fn incompatible_fields(&self, other: &Self) -> Vec<&'static str> {
+    let mut fields = vec![];
+    if self.codec_type != other.codec_type {
+      fields.push("codec_type");
+    }
+    if self.codec_id != other.codec_id {
+      fields.push("codec_id");
+    }
+    if self.width != other.width {
+      fields.push("width");
+    }
+    if self.height != other.height {
+      fields.push("height");
+    }
+    if self.extradata() != other.extradata() {
+      fields.push("extradata");
+    }
+    if self.sample_rate != other.sample_rate {
+      fields.push("sample_rate");
+    }
+    if !self.ch_layout().equal(&other.ch_layout()).unwrap_or(false) {
+      fields.push("ch_layout");
+    }
+    fields
+  }
 }
 
 impl fmt::Debug for AVCodecParameters {
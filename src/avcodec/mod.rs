@@ -1,14 +1,18 @@
 //! Everything related to `libavcodec`.
 mod bitstream;
 mod codec;
+mod codec_desc;
 mod codec_id;
 mod codec_par;
 mod packet;
 mod parser;
+mod side_data;
 
 pub use bitstream::*;
 pub use codec::*;
+pub use codec_desc::*;
 pub use codec_id::*;
 pub use codec_par::*;
 pub use packet::*;
 pub use parser::*;
+pub use side_data::*;
@@ -1,6 +1,10 @@
-use std::{fmt, ops::Drop, ptr::NonNull};
+use std::{
+  fmt,
+  ops::Drop,
+  ptr::{self, NonNull},
+};
 
-use crate::{avutil::AVRational, ffi, shared::*};
+use crate::{avutil::AVRational, error::Result, ffi, shared::*};
 
 wrap!(AVPacket: ffi::AVPacket);
 settable!(AVPacket {
@@ -19,6 +23,18 @@ impl AVPacket {
     unsafe { Self::from_raw(NonNull::new(packet).unwrap()) }
   }
 
+  /// Create an [`AVPacket`] holding a reference counted copy of `data`,
+  /// e.g. an encoded image used as cover art.
+  pub fn from_data(data: &[u8]) -> Result<Self> {
+    let mut packet = Self::new();
+    unsafe { ffi::av_new_packet(packet.as_mut_ptr(), data.len().try_into()?) }
+      .upgrade()?;
+    unsafe {
+      ptr::copy_nonoverlapping(data.as_ptr(), packet.data, data.len());
+    }
+    Ok(packet)
+  }
+
   /// Convert valid timing fields (timestamps / durations) in a packet from
   /// one timebase to another. Timestamps with unknown values
   /// (`AV_NOPTS_VALUE`) will be ignored.
@@ -52,6 +68,17 @@ impl fmt::Debug for AVPacket {
   }
 }
 
+impl Clone for AVPacket {
+  /// Create a new packet referencing the same data as `self`.
+  fn clone(&self) -> Self {
+    // Only fails on no memory, so unwrap().
+    let packet = unsafe { ffi::av_packet_clone(self.as_ptr()) }
+      .upgrade()
+      .unwrap();
+    unsafe { Self::from_raw(packet) }
+  }
+}
+
 impl Default for AVPacket {
   fn default() -> Self {
     Self::new()
@@ -68,3 +95,24 @@ impl Drop for AVPacket {
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::slice;
+
+  #[test]
+  fn test_from_data() {
+    let data = b"cover art";
+    let packet = AVPacket::from_data(data).unwrap();
+    assert_eq!(packet.size as usize, data.len());
+    let padding = ffi::AV_INPUT_BUFFER_PADDING_SIZE as usize;
+    let buffer =
+      unsafe { slice::from_raw_parts(packet.data, data.len() + padding) };
+    assert_eq!(&buffer[..data.len()], data);
+    assert!(buffer[data.len()..].iter().all(|&x| x == 0));
+
+    let packet = AVPacket::from_data(&[]).unwrap();
+    assert_eq!(packet.size, 0);
+  }
+}
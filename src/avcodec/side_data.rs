@@ -0,0 +1,241 @@
+use std::{mem, ptr, slice};
+
+use crate::{avcodec::AVCodecParameters, ffi, shared::*};
+
+/// The 3x3 transformation matrix stored in `AV_PKT_DATA_DISPLAYMATRIX` side
+/// data, which describes how the decoded video should be rotated and flipped
+/// for correct presentation, e.g. footage shot in portrait by phones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayMatrix(pub [i32; 9]);
+
+impl DisplayMatrix {
+  /// Create a pure rotation matrix of `angle` degrees counter-clockwise,
+  /// the same direction as [`Self::rotation`] returns.
+  pub fn from_rotation(angle: f64) -> Self {
+    let mut matrix = [0; 9];
+    // `av_display_rotation_set` takes a clockwise angle.
+    unsafe { ffi::av_display_rotation_set(matrix.as_mut_ptr(), -angle) };
+    Self(matrix)
+  }
+
+  /// Flip the matrix horizontally and/or vertically.
+  pub fn flip(mut self, hflip: bool, vflip: bool) -> Self {
+    unsafe {
+      ffi::av_display_matrix_flip(
+        self.0.as_mut_ptr(),
+        hflip as i32,
+        vflip as i32,
+      )
+    };
+    self
+  }
+
+  /// Get the rotation angle in degrees counter-clockwise, in the range
+  /// `[-180.0, 180.0]`. The video needs to be rotated by `-rotation()` to be
+  /// displayed correctly.
+  ///
+  /// Return NaN if the matrix is singular.
+  pub fn rotation(&self) -> f64 {
+    unsafe { ffi::av_display_rotation_get(self.0.as_ptr()) }
+  }
+
+  /// Whether the matrix mirrors the video, i.e. one of horizontal or
+  /// vertical flip is applied in addition to the rotation.
+  pub fn is_flipped(&self) -> bool {
+    let [a, b, _, c, d, ..] = self.0;
+    (a as i64) * (d as i64) - (b as i64) * (c as i64) < 0
+  }
+}
+
+impl AVCodecParameters {
+  /// Get the coded side data of type `data_type`, which applies to the
+  /// whole stream.
+  pub fn side_data(
+    &self,
+    data_type: ffi::AVPacketSideDataType,
+  ) -> Option<&[u8]> {
+    let side_data = unsafe {
+      ffi::av_packet_side_data_get(
+        self.coded_side_data,
+        self.nb_coded_side_data,
+        data_type,
+      )
+    }
+    .upgrade()?;
+    let side_data = unsafe { side_data.as_ref() };
+    Some(if side_data.size == 0 {
+      &[]
+    } else {
+      unsafe { slice::from_raw_parts(side_data.data, side_data.size) }
+    })
+  }
+
+  /// Set the coded side data of type `data_type`, the existing one of the
+  /// same type is replaced. Should be called before writing the header when
+  /// muxing.
+  pub fn set_side_data(
+    &mut self,
+    data_type: ffi::AVPacketSideDataType,
+    data: &[u8],
+  ) {
+    let parameters = unsafe { self.deref_mut() };
+    // Only fails on no memory, so unwrap().
+    let side_data = unsafe {
+      ffi::av_packet_side_data_new(
+        &mut parameters.coded_side_data,
+        &mut parameters.nb_coded_side_data,
+        data_type,
+        data.len(),
+        0,
+      )
+    }
+    .upgrade()
+    .unwrap();
+    unsafe {
+      ptr::copy_nonoverlapping(
+        data.as_ptr(),
+        side_data.as_ref().data,
+        data.len(),
+      );
+    }
+  }
+
+  /// Remove the coded side data of type `data_type`.
+  pub fn remove_side_data(&mut self, data_type: ffi::AVPacketSideDataType) {
+    let parameters = unsafe { self.deref_mut() };
+    unsafe {
+      ffi::av_packet_side_data_remove(
+        parameters.coded_side_data,
+        &mut parameters.nb_coded_side_data,
+        data_type,
+      )
+    }
+  }
+
+  /// Get the display matrix, i.e. rotation and flip, of the video.
+  pub fn display_matrix(&self) -> Option<DisplayMatrix> {
+    self
+      .side_data_as(ffi::AV_PKT_DATA_DISPLAYMATRIX)
+      .map(DisplayMatrix)
+  }
+
+  /// Set the display matrix, i.e. rotation and flip, of the video.
+  pub fn set_display_matrix(&mut self, matrix: DisplayMatrix) {
+    self.set_side_data_as(ffi::AV_PKT_DATA_DISPLAYMATRIX, &matrix.0)
+  }
+
+  /// Get the spherical (360°) video mapping.
+  pub fn spherical(&self) -> Option<ffi::AVSphericalMapping> {
+    self.side_data_as(ffi::AV_PKT_DATA_SPHERICAL)
+  }
+
+  /// Set the spherical (360°) video mapping.
+  pub fn set_spherical(&mut self, spherical: ffi::AVSphericalMapping) {
+    self.set_side_data_as(ffi::AV_PKT_DATA_SPHERICAL, &spherical)
+  }
+
+  /// Get the stereoscopic 3D packing of the video.
+  pub fn stereo3d(&self) -> Option<ffi::AVStereo3D> {
+    self.side_data_as(ffi::AV_PKT_DATA_STEREO3D)
+  }
+
+  /// Set the stereoscopic 3D packing of the video.
+  pub fn set_stereo3d(&mut self, stereo3d: ffi::AVStereo3D) {
+    self.set_side_data_as(ffi::AV_PKT_DATA_STEREO3D, &stereo3d)
+  }
+
+  /// Get the mastering display color volume (SMPTE 2086) of HDR video.
+  pub fn mastering_display_metadata(
+    &self,
+  ) -> Option<ffi::AVMasteringDisplayMetadata> {
+    self.side_data_as(ffi::AV_PKT_DATA_MASTERING_DISPLAY_METADATA)
+  }
+
+  /// Set the mastering display color volume (SMPTE 2086) of HDR video.
+  pub fn set_mastering_display_metadata(
+    &mut self,
+    metadata: ffi::AVMasteringDisplayMetadata,
+  ) {
+    self
+      .set_side_data_as(ffi::AV_PKT_DATA_MASTERING_DISPLAY_METADATA, &metadata)
+  }
+
+  /// Get the content light level (MaxCLL and MaxFALL) of HDR video.
+  pub fn content_light_level(&self) -> Option<ffi::AVContentLightMetadata> {
+    self.side_data_as(ffi::AV_PKT_DATA_CONTENT_LIGHT_LEVEL)
+  }
+
+  /// Set the content light level (MaxCLL and MaxFALL) of HDR video.
+  pub fn set_content_light_level(
+    &mut self,
+    metadata: ffi::AVContentLightMetadata,
+  ) {
+    self.set_side_data_as(ffi::AV_PKT_DATA_CONTENT_LIGHT_LEVEL, &metadata)
+  }
+
+  /// Read side data of `data_type` as a plain C struct, return `None` if it
+  /// doesn't exist or is truncated.
+  fn side_data_as<T: Copy>(
+    &self,
+    data_type: ffi::AVPacketSideDataType,
+  ) -> Option<T> {
+    let data = self.side_data(data_type)?;
+    (data.len() >= mem::size_of::<T>())
+      .then(|| unsafe { ptr::read_unaligned(data.as_ptr() as *const T) })
+  }
+
+  /// Write a plain C struct as side data of `data_type`.
+  fn set_side_data_as<T: Copy>(
+    &mut self,
+    data_type: ffi::AVPacketSideDataType,
+    value: &T,
+  ) {
+    let data = unsafe {
+      slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>())
+    };
+    self.set_side_data(data_type, data)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_display_matrix() {
+    let matrix = DisplayMatrix::from_rotation(90.0);
+    assert!((matrix.rotation() - 90.0).abs() < 1e-6);
+    assert!(!matrix.is_flipped());
+    assert!(matrix.flip(true, false).is_flipped());
+    assert!(!matrix.flip(true, true).is_flipped());
+
+    let mut parameters = AVCodecParameters::new();
+    assert!(parameters.display_matrix().is_none());
+    parameters.set_display_matrix(DisplayMatrix::from_rotation(-90.0));
+    let rotation = parameters.display_matrix().unwrap().rotation();
+    assert!((rotation + 90.0).abs() < 1e-6);
+
+    // Replaced rather than duplicated.
+    parameters.set_display_matrix(matrix);
+    assert_eq!(parameters.nb_coded_side_data, 1);
+    assert_eq!(parameters.display_matrix(), Some(matrix));
+
+    // Side data is copied along with the parameters.
+    assert_eq!(parameters.clone().display_matrix(), Some(matrix));
+
+    parameters.remove_side_data(ffi::AV_PKT_DATA_DISPLAYMATRIX);
+    assert!(parameters.display_matrix().is_none());
+  }
+
+  #[test]
+  fn test_content_light_level() {
+    let mut parameters = AVCodecParameters::new();
+    parameters.set_content_light_level(ffi::AVContentLightMetadata {
+      MaxCLL: 1000,
+      MaxFALL: 400,
+    });
+    let metadata = parameters.content_light_level().unwrap();
+    assert_eq!((metadata.MaxCLL, metadata.MaxFALL), (1000, 400));
+    assert!(parameters.mastering_display_metadata().is_none());
+  }
+}
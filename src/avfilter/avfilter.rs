@@ -1,12 +1,12 @@
 use std::{
-  ffi::CStr,
+  ffi::{c_void, CStr},
   mem::{size_of, MaybeUninit},
   ops::Drop,
   ptr::{self, NonNull},
 };
 
 use crate::{
-  avutil::{AVChannelLayout, AVFrame},
+  avutil::{capture_log, AVChannelLayout, AVFrame, AVOptions},
   error::{Result, RsmpegError},
   ffi::{self, avfilter_link},
   shared::*,
@@ -178,6 +178,17 @@ impl AVFilterContext {
   }
 }
 
+unsafe impl AVOptions for AVFilterContext {
+  fn as_opt_ptr(&self) -> *mut c_void {
+    self.as_ptr() as *mut _
+  }
+
+  // Filters are initialized when they are added to a graph.
+  fn is_initialized(&self) -> bool {
+    true
+  }
+}
+
 wrap!(AVFilterInOut: ffi::AVFilterInOut);
 
 impl AVFilterInOut {
@@ -249,16 +260,19 @@ impl AVFilterGraph {
     // parameter will contain outputs of the newly created filters.
     //
     // So the function is designed to take immutable reference to the FilterGraph
-    unsafe {
-      ffi::avfilter_graph_parse_ptr(
-        self.as_ptr() as _,
-        filter_spec.as_ptr(),
-        &mut inputs_new,
-        &mut outputs_new,
-        ptr::null_mut(),
-      )
-    }
-    .upgrade()?;
+    capture_log(|| {
+      unsafe {
+        ffi::avfilter_graph_parse_ptr(
+          self.as_ptr() as _,
+          filter_spec.as_ptr(),
+          &mut inputs_new,
+          &mut outputs_new,
+          ptr::null_mut(),
+        )
+      }
+      .upgrade()
+      .map_err(RsmpegError::from)
+    })?;
 
     // If no error, inputs and outputs pointer are dangling, manually erase
     // them *without* dropping. Do this because we need to drop inputs and
@@ -279,10 +293,13 @@ impl AVFilterGraph {
   /// Check validity and configure all the links and formats in the graph.
   pub fn config(&self) -> Result<()> {
     // ATTENTION: This takes immutable reference since it doesn't delete any filter.
-    unsafe {
-      ffi::avfilter_graph_config(self.as_ptr() as *mut _, ptr::null_mut())
-    }
-    .upgrade()?;
+    capture_log(|| {
+      unsafe {
+        ffi::avfilter_graph_config(self.as_ptr() as *mut _, ptr::null_mut())
+      }
+      .upgrade()
+      .map_err(RsmpegError::from)
+    })?;
     Ok(())
   }
 
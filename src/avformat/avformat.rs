@@ -1,16 +1,24 @@
 use std::{
   ffi::CStr,
+  mem,
   ops::Drop,
+  os::raw::{c_char, c_int, c_void},
   ptr::{self, NonNull},
+  time::Duration,
 };
 
 use crate::{
   avcodec::{
-    AVCodecParameters, AVCodecParametersMut, AVCodecParametersRef, AVCodecRef,
-    AVPacket,
+    AVCodecID, AVCodecParameters, AVCodecParametersMut, AVCodecParametersRef,
+    AVCodecRef, AVPacket,
+  },
+  avformat::{
+    AVIOContext, AVIOContextCustom, AVIOContextURL, InterruptCallback,
+  },
+  avutil::{
+    av_rescale_q, capture_log, AVDictionary, AVDictionaryMut, AVDictionaryRef,
+    AVOptions, AVRational,
   },
-  avformat::{AVIOContext, AVIOContextCustom, AVIOContextURL},
-  avutil::{AVDictionary, AVDictionaryMut, AVDictionaryRef, AVRational},
   error::{Result, RsmpegError},
   ffi,
   shared::*,
@@ -22,9 +30,48 @@ pub enum AVIOContextContainer {
   Custom(AVIOContextCustom),
 }
 
+/// How the timestamp given to [`AVFormatContextInput::seek`] is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekMode {
+  /// Seek to the nearest keyframe at or before the timestamp.
+  Backward,
+  /// Seek to any frame, non-keyframes are treated as keyframes.
+  Any,
+  /// The timestamp is a byte position in the file.
+  Byte,
+  /// The timestamp is a frame number of the given stream.
+  Frame,
+}
+
+/// Map `AVERROR_EXIT`, which is returned when the interrupt callback aborts a
+/// blocking operation, to [`RsmpegError::Interrupted`], and other errors with
+/// `f`.
+pub(crate) fn or_interrupted(
+  f: fn(c_int) -> RsmpegError,
+) -> impl Fn(c_int) -> RsmpegError {
+  move |err| match err {
+    ffi::AVERROR_EXIT => RsmpegError::Interrupted,
+    err => f(err),
+  }
+}
+
+impl SeekMode {
+  /// Get the corresponding `AVSEEK_FLAG_*` value.
+  pub fn flags(self) -> i32 {
+    (match self {
+      SeekMode::Backward => ffi::AVSEEK_FLAG_BACKWARD,
+      SeekMode::Any => ffi::AVSEEK_FLAG_ANY,
+      SeekMode::Byte => ffi::AVSEEK_FLAG_BYTE,
+      SeekMode::Frame => ffi::AVSEEK_FLAG_FRAME,
+    }) as i32
+  }
+}
+
 wrap! {
     AVFormatContextInput: ffi::AVFormatContext,
-    io_context: Option<AVIOContextContainer> = None,
+    pub io_context: Option<AVIOContextContainer> = None,
+    // Private, since the context keeps pointing to it.
+    interrupt_callback: Option<InterruptCallback> = None,
 }
 
 impl AVFormatContextInput {
@@ -42,7 +89,43 @@ impl AVFormatContextInput {
     fmt: Option<&AVInputFormat>,
     options: &mut Option<AVDictionary>,
   ) -> Result<Self> {
-    let mut input_format_context = ptr::null_mut();
+    capture_log(|| Self::open_impl(url, fmt, options, None))
+  }
+
+  /// Similar to [`Self::open`], but opening the input, finding stream info
+  /// and later blocking operations (e.g. [`Self::read_packet`]) are aborted
+  /// with [`RsmpegError::Interrupted`] once `interrupt_callback` returns
+  /// `true`.
+  pub fn open_with_interrupt(
+    url: &CStr,
+    fmt: Option<&AVInputFormat>,
+    options: &mut Option<AVDictionary>,
+    interrupt_callback: InterruptCallback,
+  ) -> Result<Self> {
+    capture_log(|| Self::open_impl(url, fmt, options, Some(interrupt_callback)))
+  }
+
+  fn open_impl(
+    url: &CStr,
+    fmt: Option<&AVInputFormat>,
+    options: &mut Option<AVDictionary>,
+    interrupt_callback: Option<InterruptCallback>,
+  ) -> Result<Self> {
+    // The interrupt callback must be set before `avformat_open_input`, so the
+    // context is allocated here. A user-supplied context is freed by
+    // `avformat_open_input` on failure.
+    let mut input_format_context = match &interrupt_callback {
+      Some(interrupt_callback) => {
+        // Only fails on no memory, so unwrap().
+        let context =
+          unsafe { ffi::avformat_alloc_context() }.upgrade().unwrap();
+        unsafe {
+          (*context.as_ptr()).interrupt_callback = interrupt_callback.as_raw();
+        }
+        context.as_ptr()
+      }
+      None => ptr::null_mut(),
+    };
     let fmt = fmt.map(|x| x.as_ptr()).unwrap_or_else(std::ptr::null) as _;
     let mut options_ptr = options
       .as_mut()
@@ -58,7 +141,7 @@ impl AVFormatContextInput {
       )
     }
     .upgrade()
-    .map_err(RsmpegError::OpenInputError)?;
+    .map_err(or_interrupted(RsmpegError::OpenInputError))?;
 
     // Forget the old options since it's ownership is transferred.
     let mut new_options = options_ptr
@@ -71,12 +154,13 @@ impl AVFormatContextInput {
     // dropping when `avformat_find_stream_info` fails.
     let mut context =
       unsafe { Self::from_raw(NonNull::new(input_format_context).unwrap()) };
+    context.interrupt_callback = interrupt_callback;
 
     unsafe {
       ffi::avformat_find_stream_info(context.as_mut_ptr(), ptr::null_mut())
     }
     .upgrade()
-    .map_err(RsmpegError::FindStreamInfoError)?;
+    .map_err(or_interrupted(RsmpegError::FindStreamInfoError))?;
 
     Ok(context)
   }
@@ -163,7 +247,17 @@ impl AVFormatContextInput {
     {
       Ok(_) => Ok(Some(packet)),
       Err(ffi::AVERROR_EOF) => Ok(None),
-      Err(x) => Err(x)?,
+      Err(x) => Err(or_interrupted(RsmpegError::AVError)(x)),
+    }
+  }
+
+  /// Return an iterator over the remaining packets, calling
+  /// [`Self::read_packet`] on each iteration. The iterator ends on EOF or
+  /// after yielding the first error.
+  pub fn packets(&mut self) -> AVPacketIter<'_> {
+    AVPacketIter {
+      input_format_context: self,
+      finished: false,
     }
   }
 
@@ -184,7 +278,7 @@ impl AVFormatContextInput {
     {
       Ok(_) => Ok(Some(())),
       Err(ffi::AVERROR_EOF) => Ok(None),
-      Err(x) => Err(x)?,
+      Err(x) => Err(or_interrupted(RsmpegError::AVError)(x)),
     }
   }
 
@@ -222,6 +316,96 @@ impl AVFormatContextInput {
       Err(e) => Err(RsmpegError::AVError(e)),
     }
   }
+
+  /// Seek to the keyframe at `timestamp` in stream `stream_index`.
+  ///
+  /// - `stream_index`: If `None`, a default stream is selected, and
+  ///   `timestamp` is in `AV_TIME_BASE` units. Otherwise `timestamp` is in
+  ///   the `time_base` of the given stream.
+  /// - `timestamp`: Interpreted according to `mode`.
+  ///
+  /// Decoders fed by this context should be flushed with
+  /// [`AVCodecContext::flush_buffers`](crate::avcodec::AVCodecContext::flush_buffers)
+  /// after a successful seek.
+  pub fn seek(
+    &mut self,
+    stream_index: Option<usize>,
+    timestamp: i64,
+    mode: SeekMode,
+  ) -> Result<()> {
+    let stream_index = stream_index.map(|x| x as i32).unwrap_or(-1);
+    unsafe {
+      ffi::av_seek_frame(
+        self.as_mut_ptr(),
+        stream_index,
+        timestamp,
+        mode.flags(),
+      )
+    }
+    .upgrade()
+    .map_err(or_interrupted(RsmpegError::SeekError))?;
+    Ok(())
+  }
+
+  /// Seek to `timestamp`, the seek is done so that the point from which all
+  /// active streams can be presented successfully will be closest to
+  /// `timestamp` and within `min_ts..=max_ts`.
+  ///
+  /// Timestamps are in the same unit as [`Self::seek`] states. `mode` of
+  /// [`SeekMode::Backward`] is ignored since `min_ts` and `max_ts` already
+  /// decide the direction.
+  pub fn seek_file(
+    &mut self,
+    stream_index: Option<usize>,
+    min_ts: i64,
+    timestamp: i64,
+    max_ts: i64,
+    mode: SeekMode,
+  ) -> Result<()> {
+    let stream_index = stream_index.map(|x| x as i32).unwrap_or(-1);
+    unsafe {
+      ffi::avformat_seek_file(
+        self.as_mut_ptr(),
+        stream_index,
+        min_ts,
+        timestamp,
+        max_ts,
+        mode.flags(),
+      )
+    }
+    .upgrade()
+    .map_err(or_interrupted(RsmpegError::SeekError))?;
+    Ok(())
+  }
+
+  /// Seek to the nearest keyframe at or before `position` of stream
+  /// `stream_index`. `position` is relative to the start time of the stream
+  /// and is rescaled to the `time_base` of the stream.
+  ///
+  /// To get the exact frame at `position`, flush the decoder with
+  /// [`AVCodecContext::flush_buffers`](crate::avcodec::AVCodecContext::flush_buffers),
+  /// then decode and discard frames until the target pts is reached.
+  pub fn seek_to(
+    &mut self,
+    stream_index: usize,
+    position: Duration,
+  ) -> Result<()> {
+    let stream = self
+      .streams()
+      .get(stream_index)
+      .ok_or(RsmpegError::SeekError(ffi::AVERROR(ffi::EINVAL)))?;
+    let timestamp = av_rescale_q(
+      i64::try_from(position.as_micros())?,
+      ffi::AV_TIME_BASE_Q,
+      stream.time_base,
+    );
+    let timestamp = if stream.start_time == ffi::AV_NOPTS_VALUE {
+      timestamp
+    } else {
+      stream.start_time + timestamp
+    };
+    self.seek(Some(stream_index), timestamp, SeekMode::Backward)
+  }
 }
 
 impl<'stream> AVFormatContextInput {
@@ -296,6 +480,112 @@ impl<'stream> AVFormatContextInput {
     // `metadata` can be null.
     NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
   }
+
+  /// Return slice of [`AVChapterRef`].
+  pub fn chapters(&'stream self) -> &'stream [AVChapterRef<'stream>] {
+    unsafe { chapters(self.chapters, self.nb_chapters) }
+  }
+
+  /// Return slice of [`AVProgramRef`], programs are only present in
+  /// multi-program containers, e.g. MPEG-TS.
+  pub fn programs(&'stream self) -> &'stream [AVProgramRef<'stream>] {
+    unsafe { programs(self.programs, self.nb_programs) }
+  }
+
+  /// Return slice of [`AVProgramMut`], e.g. for setting `discard` of the
+  /// programs which are not needed.
+  pub fn programs_mut(
+    &'stream mut self,
+  ) -> &'stream mut [AVProgramMut<'stream>] {
+    if self.nb_programs == 0 {
+      return &mut [];
+    }
+    // AVProgramMut <-> *mut ffi::AVProgram, the same as `streams_mut()`.
+    unsafe {
+      std::slice::from_raw_parts_mut(
+        self.programs as *mut AVProgramMut<'stream>,
+        self.nb_programs as usize,
+      )
+    }
+  }
+
+  /// Find the programs which contain the stream `stream_index`.
+  pub fn find_programs_from_stream(
+    &'stream self,
+    stream_index: usize,
+  ) -> impl Iterator<Item = &'stream AVProgramRef<'stream>> {
+    self.programs().iter().filter(move |program| {
+      program.stream_indexes().contains(&(stream_index as u32))
+    })
+  }
+}
+
+/// # Safety
+/// `programs` should be the program array of an `AVFormatContext` with
+/// `nb_programs` elements.
+unsafe fn programs<'program>(
+  programs: *mut *mut ffi::AVProgram,
+  nb_programs: u32,
+) -> &'program [AVProgramRef<'program>] {
+  // `programs` is null when there is no program.
+  if nb_programs == 0 {
+    return &[];
+  }
+  // AVProgramRef <-> *const ffi::AVProgram, the same as `streams()`.
+  unsafe {
+    std::slice::from_raw_parts(
+      programs as *const AVProgramRef<'program>,
+      nb_programs as usize,
+    )
+  }
+}
+
+/// # Safety
+/// `chapters` should be the chapter array of an `AVFormatContext` with
+/// `nb_chapters` elements.
+unsafe fn chapters<'chapter>(
+  chapters: *mut *mut ffi::AVChapter,
+  nb_chapters: u32,
+) -> &'chapter [AVChapterRef<'chapter>] {
+  // `chapters` is null when there is no chapter.
+  if nb_chapters == 0 {
+    return &[];
+  }
+  // AVChapterRef <-> *const ffi::AVChapter, the same as `streams()`.
+  unsafe {
+    std::slice::from_raw_parts(
+      chapters as *const AVChapterRef<'chapter>,
+      nb_chapters as usize,
+    )
+  }
+}
+
+/// Iterator over the packets of an [`AVFormatContextInput`], created by
+/// [`AVFormatContextInput::packets`].
+pub struct AVPacketIter<'a> {
+  input_format_context: &'a mut AVFormatContextInput,
+  finished: bool,
+}
+
+impl<'a> Iterator for AVPacketIter<'a> {
+  type Item = Result<AVPacket>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.finished {
+      return None;
+    }
+    let result = self.input_format_context.read_packet().transpose();
+    self.finished = !matches!(result, Some(Ok(_)));
+    result
+  }
+}
+
+impl<'a> std::iter::FusedIterator for AVPacketIter<'a> {}
+
+unsafe impl AVOptions for AVFormatContextInput {
+  fn as_opt_ptr(&self) -> *mut c_void {
+    self.as_ptr() as *mut _
+  }
 }
 
 impl Drop for AVFormatContextInput {
@@ -307,7 +597,11 @@ impl Drop for AVFormatContextInput {
 
 wrap! {
     AVFormatContextOutput: ffi::AVFormatContext,
-    io_context: Option<AVIOContextContainer> = None,
+    pub io_context: Option<AVIOContextContainer> = None,
+    // Private, since the context keeps pointing to it.
+    interrupt_callback: Option<InterruptCallback> = None,
+    // Pictures of attached picture streams, written right after the header.
+    pub cover_arts: Vec<AVPacket> = Vec::new(),
 }
 
 impl AVFormatContextOutput {
@@ -316,6 +610,28 @@ impl AVFormatContextOutput {
   pub fn create(
     filename: &CStr,
     io_context: Option<AVIOContextContainer>,
+  ) -> Result<Self> {
+    Self::create_impl(filename, io_context, None)
+  }
+
+  /// Similar to [`Self::create`], but opening the file and later blocking
+  /// operations (e.g. [`Self::write_header`]) are aborted with
+  /// [`RsmpegError::Interrupted`] once `interrupt_callback` returns `true`.
+  ///
+  /// A given [`AVIOContextContainer`] is used as is, so it should be
+  /// interruptible by itself.
+  pub fn create_with_interrupt(
+    filename: &CStr,
+    io_context: Option<AVIOContextContainer>,
+    interrupt_callback: InterruptCallback,
+  ) -> Result<Self> {
+    Self::create_impl(filename, io_context, Some(interrupt_callback))
+  }
+
+  fn create_impl(
+    filename: &CStr,
+    io_context: Option<AVIOContextContainer>,
+    interrupt_callback: Option<InterruptCallback>,
   ) -> Result<Self> {
     let mut output_format_context = ptr::null_mut();
 
@@ -333,6 +649,13 @@ impl AVFormatContextOutput {
 
     let mut output_format_context =
       unsafe { Self::from_raw(NonNull::new(output_format_context).unwrap()) };
+    if let Some(interrupt_callback) = &interrupt_callback {
+      unsafe {
+        output_format_context.deref_mut().interrupt_callback =
+          interrupt_callback.as_raw();
+      }
+    }
+    output_format_context.interrupt_callback = interrupt_callback;
 
     // Documentation of [`ffi::AVFormatContext::pb`] states:
     //
@@ -344,25 +667,74 @@ impl AVFormatContextOutput {
     // caller provides one.
     if output_format_context.oformat().flags & ffi::AVFMT_NOFILE as i32 == 0 {
       // If user provides us an `AVIOCustomContext`, use it, or we create a default one.
-      let mut io_context = match io_context {
+      let io_context = match io_context {
         Some(x) => x,
-        None => AVIOContextContainer::Url(AVIOContextURL::open(
-          filename,
-          ffi::AVIO_FLAG_WRITE,
-        )?),
+        None => AVIOContextContainer::Url(
+          match &output_format_context.interrupt_callback {
+            Some(interrupt_callback) => AVIOContextURL::open_with_interrupt(
+              filename,
+              ffi::AVIO_FLAG_WRITE,
+              interrupt_callback.clone(),
+            )?,
+            None => AVIOContextURL::open(filename, ffi::AVIO_FLAG_WRITE)?,
+          },
+        ),
       };
-      unsafe {
-        output_format_context.deref_mut().pb = match &mut io_context {
-          AVIOContextContainer::Url(ctx) => ctx.as_mut_ptr(),
-          AVIOContextContainer::Custom(ctx) => ctx.as_mut_ptr(),
-        };
-      }
-      output_format_context.io_context = Some(io_context);
+      output_format_context.attach_io_context(io_context);
     }
 
     Ok(output_format_context)
   }
 
+  /// Create a [`AVFormatContextOutput`] instance writing to the given
+  /// [`AVIOContext`] instead of a file, e.g. an in-memory buffer or a network
+  /// writer. The output format is selected by its short name, e.g. `mp4`.
+  ///
+  /// The IO context is owned by the returned instance, and the buffered data
+  /// is flushed to it by [`Self::write_trailer()`]. Muxers which rewrite the
+  /// header in the trailer (e.g. `mp4` without fragmentation) need a seekable
+  /// IO context.
+  pub fn from_io_context(
+    io_context: AVIOContextContainer,
+    format_name: &CStr,
+  ) -> Result<Self> {
+    let mut output_format_context = ptr::null_mut();
+
+    unsafe {
+      ffi::avformat_alloc_output_context2(
+        &mut output_format_context,
+        ptr::null_mut(),
+        format_name.as_ptr(),
+        ptr::null_mut(),
+      )
+    }
+    .upgrade()
+    .map_err(RsmpegError::OpenOutputError)?;
+
+    let mut output_format_context =
+      unsafe { Self::from_raw(NonNull::new(output_format_context).unwrap()) };
+
+    // Muxers with AVFMT_NOFILE do their own IO, so they cannot write to the
+    // given IO context.
+    if output_format_context.oformat().flags & ffi::AVFMT_NOFILE as i32 != 0 {
+      return Err(RsmpegError::OpenOutputError(ffi::AVERROR(ffi::EINVAL)));
+    }
+    output_format_context.attach_io_context(io_context);
+
+    Ok(output_format_context)
+  }
+
+  /// Set `pb` to the given IO context and take the ownership of it.
+  fn attach_io_context(&mut self, mut io_context: AVIOContextContainer) {
+    unsafe {
+      self.deref_mut().pb = match &mut io_context {
+        AVIOContextContainer::Url(ctx) => ctx.as_mut_ptr(),
+        AVIOContextContainer::Custom(ctx) => ctx.as_mut_ptr(),
+      };
+    }
+    self.io_context = Some(io_context);
+  }
+
   /// Allocate the stream private data and write the stream header to an
   /// output media file.
   ///
@@ -379,16 +751,24 @@ impl AVFormatContextOutput {
       .map(|x| x.into_raw().as_ptr())
       .unwrap_or_else(ptr::null_mut);
 
-    let result = unsafe {
-      ffi::avformat_write_header(self.as_mut_ptr(), &mut dict_ptr as _)
-    };
+    let result = capture_log(|| {
+      unsafe {
+        ffi::avformat_write_header(self.as_mut_ptr(), &mut dict_ptr as _)
+      }
+      .upgrade()
+      .map_err(or_interrupted(RsmpegError::WriteHeaderError))
+    });
 
     // Move back the ownership if not consumed.
     *dict = dict_ptr
       .upgrade()
       .map(|x| unsafe { AVDictionary::from_raw(x) });
 
-    result.upgrade().map_err(RsmpegError::WriteHeaderError)?;
+    result?;
+
+    for mut picture in std::mem::take(&mut self.cover_arts) {
+      self.write_frame(&mut picture)?;
+    }
 
     Ok(())
   }
@@ -398,7 +778,12 @@ impl AVFormatContextOutput {
   pub fn write_trailer(&mut self) -> Result<()> {
     unsafe { ffi::av_write_trailer(self.as_mut_ptr()) }
       .upgrade()
-      .map_err(RsmpegError::WriteTrailerError)?;
+      .map_err(or_interrupted(RsmpegError::WriteTrailerError))?;
+    // Make sure everything reaches the custom IO context before the caller
+    // inspects its destination.
+    if let Some(AVIOContextContainer::Custom(ctx)) = self.io_context.as_mut() {
+      unsafe { ffi::avio_flush(ctx.as_mut_ptr()) };
+    }
     Ok(())
   }
 
@@ -422,7 +807,8 @@ impl AVFormatContextOutput {
   /// [`Self::interleaved_write_frame()`] instead of this function.
   pub fn write_frame(&mut self, packet: &mut AVPacket) -> Result<()> {
     unsafe { ffi::av_write_frame(self.as_mut_ptr(), packet.as_mut_ptr()) }
-      .upgrade()?;
+      .upgrade()
+      .map_err(or_interrupted(RsmpegError::AVError))?;
     Ok(())
   }
 
@@ -440,9 +826,40 @@ impl AVFormatContextOutput {
       ffi::av_interleaved_write_frame(self.as_mut_ptr(), packet.as_mut_ptr())
     }
     .upgrade()
-    .map_err(RsmpegError::InterleavedWriteFrameError)?;
+    .map_err(or_interrupted(RsmpegError::InterleavedWriteFrameError))?;
     Ok(())
   }
+
+  /// Add a cover art to a media file (e.g. MP4, M4A or MP3), should be
+  /// called by the user before [`Self::write_header()`]. `codecpar` describes
+  /// the picture, e.g. `AV_CODEC_ID_MJPEG` or `AV_CODEC_ID_PNG`, and `picture`
+  /// holds the encoded image, it's written right after the header.
+  ///
+  /// Return the index of the attached picture stream.
+  pub fn add_cover_art(
+    &mut self,
+    mut codecpar: AVCodecParameters,
+    mut picture: AVPacket,
+  ) -> usize {
+    codecpar.set_codec_type(ffi::AVMEDIA_TYPE_VIDEO);
+    let stream_index = {
+      let mut stream = self.new_stream();
+      stream.set_codecpar(codecpar);
+      // Some muxers (e.g. mov) require the disposition to be exactly
+      // `ATTACHED_PIC`.
+      stream.set_disposition(Disposition::ATTACHED_PIC);
+      stream.index as usize
+    };
+
+    picture.set_stream_index(stream_index as i32);
+    picture.set_pts(0);
+    picture.set_dts(0);
+    picture.set_duration(0);
+    picture.set_flags(picture.flags | ffi::AV_PKT_FLAG_KEY as i32);
+    self.cover_arts.push(picture);
+
+    stream_index
+  }
 }
 
 impl<'stream> AVFormatContextOutput {
@@ -506,6 +923,92 @@ impl<'stream> AVFormatContextOutput {
 
     unsafe { AVStreamMut::from_raw(new_stream) }
   }
+
+  /// Return slice of [`AVChapterRef`].
+  pub fn chapters(&'stream self) -> &'stream [AVChapterRef<'stream>] {
+    unsafe { chapters(self.chapters, self.nb_chapters) }
+  }
+
+  /// Add a new chapter to a media file, should be called by the user before
+  /// [`Self::write_header()`]. `start` and `end` are in `time_base` units,
+  /// metadata (e.g. `title`) can be set on the returned chapter.
+  pub fn add_chapter(
+    &'stream mut self,
+    id: i64,
+    time_base: AVRational,
+    start: i64,
+    end: i64,
+  ) -> AVChapterMut<'stream> {
+    // The chapter is freed by `avformat_free_context`, so it's allocated
+    // with av_mallocz() the same as `avpriv_new_chapter`.
+    let chapter = unsafe { ffi::av_mallocz(mem::size_of::<ffi::AVChapter>()) }
+      as *mut ffi::AVChapter;
+    // Only fails on no memory, so unwrap().
+    let chapter = NonNull::new(chapter).unwrap();
+    unsafe {
+      chapter.as_ptr().write(ffi::AVChapter {
+        id,
+        time_base,
+        start,
+        end,
+        metadata: ptr::null_mut(),
+      });
+    }
+
+    let context = unsafe { self.deref_mut() };
+    unsafe {
+      ffi::av_dynarray_add_nofree(
+        &mut context.chapters as *mut _ as *mut c_void,
+        &mut context.nb_chapters as *mut u32 as *mut c_int,
+        chapter.as_ptr() as *mut c_void,
+      )
+    }
+    .upgrade()
+    .unwrap();
+
+    unsafe { AVChapterMut::from_raw(chapter) }
+  }
+
+  /// Return slice of [`AVProgramRef`].
+  pub fn programs(&'stream self) -> &'stream [AVProgramRef<'stream>] {
+    unsafe { programs(self.programs, self.nb_programs) }
+  }
+
+  /// Add a new program with the given `id` to a media file, the existing
+  /// program is returned if there is already one with the same `id`. Should
+  /// be called by the user before [`Self::write_header()`].
+  ///
+  /// For MPEG-TS, `id` is the service id, and the `service_name` and
+  /// `service_provider` metadata of the program are written to the SDT.
+  pub fn new_program(&'stream mut self, id: i32) -> AVProgramMut<'stream> {
+    // Only fails on no memory, so unwrap().
+    let program = unsafe { ffi::av_new_program(self.as_mut_ptr(), id) }
+      .upgrade()
+      .unwrap();
+    unsafe { AVProgramMut::from_raw(program) }
+  }
+
+  /// Add the stream `stream_index` to the program with the given `id`. Does
+  /// nothing if there is no such program or the stream is already in it.
+  pub fn add_stream_to_program(
+    &mut self,
+    program_id: i32,
+    stream_index: usize,
+  ) {
+    unsafe {
+      ffi::av_program_add_stream_index(
+        self.as_mut_ptr(),
+        program_id,
+        stream_index as u32,
+      )
+    }
+  }
+}
+
+unsafe impl AVOptions for AVFormatContextOutput {
+  fn as_opt_ptr(&self) -> *mut c_void {
+    self.as_ptr() as *mut _
+  }
 }
 
 impl Drop for AVFormatContextOutput {
@@ -533,6 +1036,119 @@ impl AVInputFormat {
       .upgrade()
       .map(|x| unsafe { AVInputFormatRef::from_raw(x) })
   }
+
+  /// Guess the input format of `buf`, which holds the leading bytes of the
+  /// media, without opening it.
+  ///
+  /// - `filename_hint`: file name used for extension based guessing.
+  ///
+  /// Return the detected format and its score (at most
+  /// [`ffi::AVPROBE_SCORE_MAX`]), `None` if no format matches. If the score is
+  /// lower than `AVPROBE_SCORE_MAX / 4`, retrying with a larger buffer is
+  /// recommended.
+  pub fn probe(
+    buf: &[u8],
+    filename_hint: Option<&CStr>,
+  ) -> Option<(AVInputFormatRef<'static>, i32)> {
+    // The probe buffer needs AVPROBE_PADDING_SIZE zeroed bytes at its tail.
+    let mut padded =
+      Vec::with_capacity(buf.len() + ffi::AVPROBE_PADDING_SIZE as usize);
+    padded.extend_from_slice(buf);
+    padded.resize(buf.len() + ffi::AVPROBE_PADDING_SIZE as usize, 0);
+
+    let probe_data = ffi::AVProbeData {
+      filename: filename_hint.unwrap_or_default().as_ptr(),
+      buf: padded.as_mut_ptr(),
+      buf_size: buf.len().try_into().ok()?,
+      mime_type: ptr::null(),
+    };
+
+    let mut score = 0;
+    unsafe { ffi::av_probe_input_format3(&probe_data, 1, &mut score) }
+      .upgrade()
+      .map(|x| (unsafe { AVInputFormatRef::from_raw(x) }, score))
+  }
+
+  /// Probe the bytestream of the given [`AVIOContext`] (e.g. an
+  /// [`AVIOContextCustom`]) to determine its input format. The probe buffer
+  /// is enlarged until the score is high enough or `max_probe_size` (zero
+  /// for default) is reached.
+  ///
+  /// The probed data is kept in the IO context, so it can still be passed to
+  /// [`AVFormatContextInput::from_io_context`] afterwards.
+  ///
+  /// Return the detected format and its score.
+  pub fn probe_io(
+    io_context: &mut AVIOContext,
+    filename_hint: Option<&CStr>,
+    max_probe_size: u32,
+  ) -> Result<(AVInputFormatRef<'static>, i32)> {
+    let mut format = ptr::null();
+    let score = unsafe {
+      ffi::av_probe_input_buffer2(
+        io_context.as_mut_ptr(),
+        &mut format,
+        filename_hint.map(|x| x.as_ptr()).unwrap_or(ptr::null()),
+        ptr::null_mut(),
+        0,
+        max_probe_size,
+      )
+    }
+    .upgrade()?;
+    let format = format.upgrade_or(ffi::AVERROR_INVALIDDATA)?;
+    Ok((unsafe { AVInputFormatRef::from_raw(format) }, score))
+  }
+
+  /// Get the short name of the input format.
+  pub fn name(&self) -> &CStr {
+    unsafe { CStr::from_ptr(self.name) }
+  }
+
+  /// Get the descriptive name of the input format.
+  pub fn long_name(&self) -> Option<&CStr> {
+    self
+      .long_name
+      .upgrade()
+      .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+  }
+
+  /// Get the capability flags of the demuxer.
+  pub fn flags(&self) -> FormatFlags {
+    FormatFlags::from_bits_retain(self.flags)
+  }
+
+  /// Get the filename extensions of the format, e.g. `["mkv", "mk3d"]`.
+  /// They are only used when the content can't be probed.
+  pub fn extensions(&self) -> Vec<&str> {
+    split_list(self.extensions)
+  }
+
+  /// Get the MIME types the demuxer matches when probing.
+  pub fn mime_types(&self) -> Vec<&str> {
+    split_list(self.mime_type)
+  }
+
+  /// Create an iterator on all the demuxers of the linked FFmpeg.
+  pub fn iterate() -> AVInputFormatIter {
+    AVInputFormatIter {
+      opaque: ptr::null_mut(),
+    }
+  }
+}
+
+/// Iterator of the registered demuxers, created by
+/// [`AVInputFormat::iterate`].
+pub struct AVInputFormatIter {
+  opaque: *mut c_void,
+}
+
+impl std::iter::Iterator for AVInputFormatIter {
+  type Item = AVInputFormatRef<'static>;
+  fn next(&mut self) -> Option<Self::Item> {
+    unsafe { ffi::av_demuxer_iterate(&mut self.opaque) }
+      .upgrade()
+      .map(|x| unsafe { AVInputFormatRef::from_raw(x) })
+  }
 }
 
 wrap_ref!(AVOutputFormat: ffi::AVOutputFormat);
@@ -554,12 +1170,186 @@ impl AVOutputFormat {
       .upgrade()
       .map(|x| unsafe { AVOutputFormatRef::from_raw(x) })
   }
+
+  /// Create an iterator on all the muxers of the linked FFmpeg.
+  pub fn iterate() -> AVOutputFormatIter {
+    AVOutputFormatIter {
+      opaque: ptr::null_mut(),
+    }
+  }
+
+  /// Get the short name of the output format.
+  pub fn name(&self) -> &CStr {
+    unsafe { CStr::from_ptr(self.name) }
+  }
+
+  /// Get the descriptive name of the output format.
+  pub fn long_name(&self) -> Option<&CStr> {
+    self
+      .long_name
+      .upgrade()
+      .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+  }
+
+  /// Get the capability flags of the muxer.
+  pub fn flags(&self) -> FormatFlags {
+    FormatFlags::from_bits_retain(self.flags)
+  }
+
+  /// Get the filename extensions of the format, e.g. `["mp4"]`.
+  pub fn extensions(&self) -> Vec<&str> {
+    split_list(self.extensions)
+  }
+
+  /// Get the MIME types of the format.
+  pub fn mime_types(&self) -> Vec<&str> {
+    split_list(self.mime_type)
+  }
+
+  /// Get the default video codec, `None` if the format has no video.
+  pub fn video_codec(&self) -> Option<AVCodecID> {
+    (self.video_codec != ffi::AV_CODEC_ID_NONE).then_some(self.video_codec)
+  }
+
+  /// Get the default audio codec, `None` if the format has no audio.
+  pub fn audio_codec(&self) -> Option<AVCodecID> {
+    (self.audio_codec != ffi::AV_CODEC_ID_NONE).then_some(self.audio_codec)
+  }
+
+  /// Get the default subtitle codec, `None` if the format has no subtitles.
+  pub fn subtitle_codec(&self) -> Option<AVCodecID> {
+    (self.subtitle_codec != ffi::AV_CODEC_ID_NONE)
+      .then_some(self.subtitle_codec)
+  }
+
+  /// Test if the muxer can store `codec_id` with the given
+  /// `std_compliance` (e.g. [`ffi::FF_COMPLIANCE_NORMAL`]).
+  ///
+  /// Return `None` if the muxer doesn't know.
+  pub fn query_codec(
+    &self,
+    codec_id: AVCodecID,
+    std_compliance: i32,
+  ) -> Option<bool> {
+    match unsafe {
+      ffi::avformat_query_codec(self.as_ptr(), codec_id, std_compliance)
+    } {
+      ret if ret < 0 => None,
+      ret => Some(ret == 1),
+    }
+  }
+}
+
+/// Iterator of the registered muxers, created by [`AVOutputFormat::iterate`].
+pub struct AVOutputFormatIter {
+  opaque: *mut c_void,
+}
+
+impl std::iter::Iterator for AVOutputFormatIter {
+  type Item = AVOutputFormatRef<'static>;
+  fn next(&mut self) -> Option<Self::Item> {
+    unsafe { ffi::av_muxer_iterate(&mut self.opaque) }
+      .upgrade()
+      .map(|x| unsafe { AVOutputFormatRef::from_raw(x) })
+  }
+}
+
+/// Split a nullable comma separated list of a format.
+fn split_list<'a>(list: *const c_char) -> Vec<&'a str> {
+  list
+    .upgrade()
+    .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    .and_then(|x| x.to_str().ok())
+    .map(|x| x.split(',').filter(|x| !x.is_empty()).collect())
+    .unwrap_or_default()
+}
+
+bitflags::bitflags! {
+  /// Capabilities of a muxer or demuxer, a combination of `AVFMT_*` flags.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct FormatFlags: i32 {
+    /// The format doesn't need a file, i.e. it does its own IO, e.g. devices.
+    const NOFILE = ffi::AVFMT_NOFILE as i32;
+    /// The filename needs a `%d` pattern, e.g. image sequences.
+    const NEEDNUMBER = ffi::AVFMT_NEEDNUMBER as i32;
+    /// The format is experimental and needs a strict compliance setting.
+    const EXPERIMENTAL = ffi::AVFMT_EXPERIMENTAL as i32;
+    const SHOW_IDS = ffi::AVFMT_SHOW_IDS as i32;
+    /// The format wants global headers, i.e. the extradata of the encoders.
+    const GLOBALHEADER = ffi::AVFMT_GLOBALHEADER as i32;
+    const NOTIMESTAMPS = ffi::AVFMT_NOTIMESTAMPS as i32;
+    const GENERIC_INDEX = ffi::AVFMT_GENERIC_INDEX as i32;
+    const TS_DISCONT = ffi::AVFMT_TS_DISCONT as i32;
+    const VARIABLE_FPS = ffi::AVFMT_VARIABLE_FPS as i32;
+    const NODIMENSIONS = ffi::AVFMT_NODIMENSIONS as i32;
+    const NOSTREAMS = ffi::AVFMT_NOSTREAMS as i32;
+    const NOBINSEARCH = ffi::AVFMT_NOBINSEARCH as i32;
+    const NOGENSEARCH = ffi::AVFMT_NOGENSEARCH as i32;
+    const NO_BYTE_SEEK = ffi::AVFMT_NO_BYTE_SEEK as i32;
+    const ALLOW_FLUSH = ffi::AVFMT_ALLOW_FLUSH as i32;
+    const TS_NONSTRICT = ffi::AVFMT_TS_NONSTRICT as i32;
+    const TS_NEGATIVE = ffi::AVFMT_TS_NEGATIVE as i32;
+    const SEEK_TO_PTS = ffi::AVFMT_SEEK_TO_PTS as i32;
+
+    // Keep unknown flags of newer FFmpeg versions.
+    const _ = !0;
+  }
+}
+
+bitflags::bitflags! {
+  /// Stream disposition, a combination of `AV_DISPOSITION_*` flags.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct Disposition: i32 {
+    const DEFAULT = ffi::AV_DISPOSITION_DEFAULT as i32;
+    const DUB = ffi::AV_DISPOSITION_DUB as i32;
+    const ORIGINAL = ffi::AV_DISPOSITION_ORIGINAL as i32;
+    const COMMENT = ffi::AV_DISPOSITION_COMMENT as i32;
+    const LYRICS = ffi::AV_DISPOSITION_LYRICS as i32;
+    const KARAOKE = ffi::AV_DISPOSITION_KARAOKE as i32;
+    const FORCED = ffi::AV_DISPOSITION_FORCED as i32;
+    const HEARING_IMPAIRED = ffi::AV_DISPOSITION_HEARING_IMPAIRED as i32;
+    const VISUAL_IMPAIRED = ffi::AV_DISPOSITION_VISUAL_IMPAIRED as i32;
+    const CLEAN_EFFECTS = ffi::AV_DISPOSITION_CLEAN_EFFECTS as i32;
+    /// The stream is stored in the file as an attached picture, e.g. cover
+    /// art, see [`AVStream::attached_pic`].
+    const ATTACHED_PIC = ffi::AV_DISPOSITION_ATTACHED_PIC as i32;
+    const TIMED_THUMBNAILS = ffi::AV_DISPOSITION_TIMED_THUMBNAILS as i32;
+    const NON_DIEGETIC = ffi::AV_DISPOSITION_NON_DIEGETIC as i32;
+    const CAPTIONS = ffi::AV_DISPOSITION_CAPTIONS as i32;
+    const DESCRIPTIONS = ffi::AV_DISPOSITION_DESCRIPTIONS as i32;
+    const METADATA = ffi::AV_DISPOSITION_METADATA as i32;
+    const DEPENDENT = ffi::AV_DISPOSITION_DEPENDENT as i32;
+    const STILL_IMAGE = ffi::AV_DISPOSITION_STILL_IMAGE as i32;
+
+    // Keep unknown flags of newer FFmpeg versions.
+    const _ = !0;
+  }
+}
+
+/// Replace the metadata dictionary owned by a stream, chapter or program,
+/// freeing the old one.
+///
+/// # Safety
+///
+/// `metadata` should be null or point to a dictionary owned by its holder.
+unsafe fn replace_metadata(
+  metadata: &mut *mut ffi::AVDictionary,
+  dict: Option<AVDictionary>,
+) {
+  // Drop the old_dict
+  let _ = NonNull::new(*metadata).map(|x| unsafe { AVDictionary::from_raw(x) });
+
+  // Move in the new dict.
+  *metadata = dict
+    .map(|x| x.into_raw().as_ptr())
+    .unwrap_or(ptr::null_mut());
 }
 
 wrap_ref_mut!(#[repr(transparent)] AVStream: ffi::AVStream);
 settable!(AVStream {
   time_base: AVRational,
-  duration: i64
+  duration: i64,
+  discard: ffi::AVDiscard
 });
 
 impl AVStream {
@@ -598,6 +1388,36 @@ impl AVStream {
     let result = unsafe { ffi::av_stream_get_end_pts(self.as_ptr()) };
     (result >= 0).then_some(result)
   }
+
+  /// Get the disposition of current stream.
+  pub fn disposition(&self) -> Disposition {
+    Disposition::from_bits_retain(self.disposition)
+  }
+
+  /// Set the disposition of current stream, should be called before
+  /// writing the header when muxing.
+  pub fn set_disposition(&mut self, disposition: Disposition) {
+    unsafe {
+      self.deref_mut().disposition = disposition.bits();
+    }
+  }
+
+  /// Get a new reference of the attached picture (e.g. cover art) of current
+  /// stream, return `None` if the stream doesn't have
+  /// [`Disposition::ATTACHED_PIC`].
+  pub fn attached_pic(&self) -> Option<AVPacket> {
+    if !self.disposition().contains(Disposition::ATTACHED_PIC)
+      || self.attached_pic.size <= 0
+    {
+      return None;
+    }
+    let mut packet = AVPacket::new();
+    // Only fails on no memory, so unwrap().
+    unsafe { ffi::av_packet_ref(packet.as_mut_ptr(), &self.attached_pic) }
+      .upgrade()
+      .unwrap();
+    Some(packet)
+  }
 }
 
 impl<'stream> AVStream {
@@ -642,24 +1462,125 @@ impl<'stream> AVStream {
 
   /// Set metadata of current [`AVStream`].
   pub fn set_metadata(&mut self, dict: Option<AVDictionary>) {
-    // Drop the old_dict
-    let _ =
-      NonNull::new(self.metadata).map(|x| unsafe { AVDictionary::from_raw(x) });
+    unsafe { replace_metadata(&mut self.deref_mut().metadata, dict) }
+  }
+}
+
+wrap_ref_mut!(#[repr(transparent)] AVChapter: ffi::AVChapter);
+settable!(AVChapter {
+  id: i64,
+  time_base: AVRational,
+  start: i64,
+  end: i64
+});
+
+impl AVChapter {
+  /// Get the start time of the chapter, rescaled from its `time_base`.
+  /// Negative timestamps are clamped to zero.
+  pub fn start_time(&self) -> Duration {
+    self.ts_to_duration(self.start)
+  }
+
+  /// Get the end time of the chapter, rescaled from its `time_base`.
+  /// Negative timestamps are clamped to zero.
+  pub fn end_time(&self) -> Duration {
+    self.ts_to_duration(self.end)
+  }
+
+  fn ts_to_duration(&self, ts: i64) -> Duration {
+    let micros = av_rescale_q(ts, self.time_base, ffi::AV_TIME_BASE_Q);
+    Duration::from_micros(micros.max(0) as u64)
+  }
+}
+
+impl<'chapter> AVChapter {
+  /// Get metadata of current chapter.
+  pub fn metadata(&'chapter self) -> Option<AVDictionaryRef<'chapter>> {
+    NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
+  }
+
+  /// Set metadata of current [`AVChapter`].
+  pub fn set_metadata(&mut self, dict: Option<AVDictionary>) {
+    unsafe { replace_metadata(&mut self.deref_mut().metadata, dict) }
+  }
+}
+
+wrap_ref_mut!(#[repr(transparent)] AVProgram: ffi::AVProgram);
+settable!(AVProgram {
+  discard: ffi::AVDiscard,
+  program_num: i32,
+  pmt_pid: i32,
+  pcr_pid: i32
+});
 
-    // Move in the new dict.
+impl<'program> AVProgram {
+  /// Get indexes of the streams belonging to the program.
+  pub fn stream_indexes(&'program self) -> &'program [u32] {
+    if self.nb_stream_indexes == 0 {
+      return &[];
+    }
     unsafe {
-      self.deref_mut().metadata = dict
-        .map(|x| x.into_raw().as_ptr())
-        .unwrap_or(ptr::null_mut());
+      std::slice::from_raw_parts(
+        self.stream_index,
+        self.nb_stream_indexes as usize,
+      )
     }
   }
+
+  /// Get metadata of current program.
+  pub fn metadata(&'program self) -> Option<AVDictionaryRef<'program>> {
+    NonNull::new(self.metadata).map(|x| unsafe { AVDictionaryRef::from_raw(x) })
+  }
+
+  /// Set metadata of current [`AVProgram`].
+  pub fn set_metadata(&mut self, dict: Option<AVDictionary>) {
+    unsafe { replace_metadata(&mut self.deref_mut().metadata, dict) }
+  }
 }
 
 #[cfg(test)]
 mod test {
   use super::*;
+  use crate::avformat::CancellationToken;
   use cstr::cstr;
 
+  #[test]
+  fn test_muxer_iterate() {
+    let mp4 = AVOutputFormat::iterate()
+      .find(|x| x.name() == cstr!("mp4"))
+      .unwrap();
+    assert_eq!(mp4.extensions(), ["mp4"]);
+    assert_eq!(mp4.mime_types(), ["video/mp4"]);
+    assert_eq!(mp4.audio_codec(), Some(ffi::AV_CODEC_ID_AAC));
+    assert_eq!(mp4.subtitle_codec(), None);
+    assert!(mp4.flags().contains(FormatFlags::GLOBALHEADER));
+    assert_eq!(
+      mp4.query_codec(ffi::AV_CODEC_ID_H264, ffi::FF_COMPLIANCE_NORMAL as i32),
+      Some(true)
+    );
+    assert_eq!(
+      mp4.query_codec(
+        ffi::AV_CODEC_ID_MPEG2VIDEO,
+        ffi::FF_COMPLIANCE_NORMAL as i32
+      ),
+      Some(true)
+    );
+    assert_eq!(
+      mp4.query_codec(ffi::AV_CODEC_ID_WMAV2, ffi::FF_COMPLIANCE_NORMAL as i32),
+      Some(false)
+    );
+  }
+
+  #[test]
+  fn test_demuxer_iterate() {
+    let matroska = AVInputFormat::iterate()
+      .find(|x| x.name() == cstr!("matroska,webm"))
+      .unwrap();
+    assert!(matroska.extensions().contains(&"mkv"));
+    assert!(matroska.mime_types().contains(&"video/webm"));
+    assert!(!matroska.flags().contains(FormatFlags::NOFILE));
+  }
+
   #[test]
   fn test_find_input_format() {
     let name = cstr!("mpeg");
@@ -679,4 +1600,104 @@ mod test {
     let name = cstr!("__random__");
     assert!(AVInputFormat::find(name).is_none());
   }
+
+  #[test]
+  fn test_probe_input_format() {
+    let data = std::fs::read("tests/assets/vids/bear.mp4").unwrap();
+    let (format, score) = AVInputFormat::probe(&data[..4096], None).unwrap();
+    assert!(format.name().to_str().unwrap().contains("mp4"));
+    assert!(score > ffi::AVPROBE_SCORE_MAX as i32 / 4);
+
+    let (format, _) =
+      AVInputFormat::probe(&[], Some(cstr!("bear.mp4"))).unwrap();
+    assert!(format.name().to_str().unwrap().contains("mp4"));
+
+    assert!(AVInputFormat::probe(&[], None).is_none());
+  }
+
+  #[test]
+  fn test_open_with_interrupt() {
+    let url = cstr!("tests/assets/vids/bear.mp4");
+
+    let token = CancellationToken::new();
+    let input_format_context = AVFormatContextInput::open_with_interrupt(
+      url,
+      None,
+      &mut None,
+      InterruptCallback::from_token(
+        token.clone(),
+        Some(std::time::Instant::now() + Duration::from_secs(60)),
+      ),
+    );
+    assert!(input_format_context.is_ok());
+
+    token.cancel();
+    let input_format_context = AVFormatContextInput::open_with_interrupt(
+      url,
+      None,
+      &mut None,
+      InterruptCallback::from_token(token, None),
+    );
+    assert!(matches!(
+      input_format_context,
+      Err(RsmpegError::Interrupted)
+    ));
+
+    let input_format_context = AVFormatContextInput::open_with_interrupt(
+      url,
+      None,
+      &mut None,
+      InterruptCallback::from_token(
+        CancellationToken::new(),
+        Some(std::time::Instant::now()),
+      ),
+    );
+    assert!(matches!(
+      input_format_context,
+      Err(RsmpegError::Interrupted)
+    ));
+  }
+
+  #[test]
+  fn test_packets() {
+    let mut input_format_context = AVFormatContextInput::open(
+      cstr!("tests/assets/vids/bear.mp4"),
+      None,
+      &mut None,
+    )
+    .unwrap();
+    let nb_streams = input_format_context.streams().len();
+    let mut count = 0;
+    for packet in input_format_context.packets() {
+      assert!((packet.unwrap().stream_index as usize) < nb_streams);
+      count += 1;
+    }
+    assert!(count > 0);
+    assert!(input_format_context.packets().next().is_none());
+  }
+
+  #[test]
+  fn test_seek_to() {
+    let mut input_format_context = AVFormatContextInput::open(
+      cstr!("tests/assets/vids/bear.mp4"),
+      None,
+      &mut None,
+    )
+    .unwrap();
+    let (video_index, _) = input_format_context
+      .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+      .unwrap()
+      .unwrap();
+
+    input_format_context
+      .seek_to(video_index, Duration::from_millis(500))
+      .unwrap();
+    let packet = input_format_context.read_packet().unwrap().unwrap();
+    assert!(packet.pts != ffi::AV_NOPTS_VALUE);
+
+    assert!(matches!(
+      input_format_context.seek_to(100, Duration::ZERO),
+      Err(RsmpegError::SeekError(_))
+    ));
+  }
 }
@@ -1,25 +1,37 @@
 use std::{
   ffi::CStr,
+  io::{self, Read, Seek, SeekFrom, Write},
   ops::{Deref, Drop},
   ptr::{self, NonNull},
   slice,
+  sync::{Arc, Mutex},
 };
 
-use crate::{avutil::AVMem, error::*, ffi, shared::*};
+use crate::{
+  avformat::{or_interrupted, InterruptCallback},
+  avutil::AVMem,
+  error::*,
+  ffi,
+  shared::*,
+};
 
 wrap!(AVIOContext: ffi::AVIOContext);
 
-pub struct AVIOContextURL(AVIOContext);
+pub struct AVIOContextURL {
+  inner: AVIOContext,
+  // Called by the IO context until it's closed in `drop()`.
+  _interrupt_callback: Option<InterruptCallback>,
+}
 
 impl Deref for AVIOContextURL {
   type Target = AVIOContext;
   fn deref(&self) -> &Self::Target {
-    &self.0
+    &self.inner
   }
 }
 impl std::ops::DerefMut for AVIOContextURL {
   fn deref_mut(&mut self) -> &mut Self::Target {
-    &mut self.0
+    &mut self.inner
   }
 }
 
@@ -34,9 +46,40 @@ impl AVIOContextURL {
     unsafe { ffi::avio_open(&mut io_context, url.as_ptr(), flags as _) }
       .upgrade()
       .map_err(RsmpegError::AVIOOpenError)?;
-    Ok(Self(unsafe {
-      AVIOContext::from_raw(NonNull::new(io_context).unwrap())
-    }))
+    Ok(Self {
+      inner: unsafe {
+        AVIOContext::from_raw(NonNull::new(io_context).unwrap())
+      },
+      _interrupt_callback: None,
+    })
+  }
+
+  /// Similar to [`Self::open`], but blocking IO on the resource is aborted
+  /// once `interrupt_callback` returns `true`. The callback is owned by the
+  /// returned [`AVIOContextURL`].
+  pub fn open_with_interrupt(
+    url: &CStr,
+    flags: u32,
+    interrupt_callback: InterruptCallback,
+  ) -> Result<Self> {
+    let mut io_context = ptr::null_mut();
+    unsafe {
+      ffi::avio_open2(
+        &mut io_context,
+        url.as_ptr(),
+        flags as _,
+        &interrupt_callback.as_raw(),
+        ptr::null_mut(),
+      )
+    }
+    .upgrade()
+    .map_err(or_interrupted(RsmpegError::AVIOOpenError))?;
+    Ok(Self {
+      inner: unsafe {
+        AVIOContext::from_raw(NonNull::new(io_context).unwrap())
+      },
+      _interrupt_callback: Some(interrupt_callback),
+    })
   }
 }
 
@@ -171,8 +214,158 @@ impl AVIOContextCustom {
   }
 }
 
+/// Buffer size used by the `std::io` based constructors of
+/// [`AVIOContextCustom`], same as FFmpeg's internal IO buffer size.
+const IO_BUFFER_SIZE: usize = 32768;
+
+impl AVIOContextCustom {
+  /// Create a readable [`AVIOContextCustom`] backed by a seekable
+  /// [`Read`] implementor, e.g. a [`std::fs::File`] or a
+  /// [`std::io::Cursor`].
+  pub fn from_reader<R: Read + Seek + Send + 'static>(reader: R) -> Self {
+    let reader = Arc::new(Mutex::new(reader));
+    let seeker = reader.clone();
+    Self::alloc_context(
+      AVMem::new(IO_BUFFER_SIZE),
+      false,
+      vec![],
+      Some(Box::new(move |_, buf| {
+        read_packet(&mut *reader.lock().unwrap(), buf)
+      })),
+      None,
+      Some(Box::new(move |_, offset, whence| {
+        seek(&mut *seeker.lock().unwrap(), offset, whence)
+      })),
+    )
+  }
+
+  /// Create a readable [`AVIOContextCustom`] backed by a [`Read`] implementor
+  /// which cannot seek, e.g. a socket or a pipe.
+  pub fn from_read_stream<R: Read + Send + 'static>(mut reader: R) -> Self {
+    Self::alloc_context(
+      AVMem::new(IO_BUFFER_SIZE),
+      false,
+      vec![],
+      Some(Box::new(move |_, buf| read_packet(&mut reader, buf))),
+      None,
+      None,
+    )
+  }
+
+  /// Create a writable [`AVIOContextCustom`] backed by a seekable [`Write`]
+  /// implementor. Seeking is needed by muxers which rewrite the header in
+  /// the trailer, e.g. mp4.
+  pub fn from_writer<W: Write + Seek + Send + 'static>(writer: W) -> Self {
+    let writer = Arc::new(Mutex::new(writer));
+    let seeker = writer.clone();
+    Self::alloc_context(
+      AVMem::new(IO_BUFFER_SIZE),
+      true,
+      vec![],
+      None,
+      Some(Box::new(move |_, buf| {
+        write_packet(&mut *writer.lock().unwrap(), buf)
+      })),
+      Some(Box::new(move |_, offset, whence| {
+        seek(&mut *seeker.lock().unwrap(), offset, whence)
+      })),
+    )
+  }
+
+  /// Create a writable [`AVIOContextCustom`] backed by a [`Write`]
+  /// implementor which cannot seek, e.g. a socket or a pipe.
+  pub fn from_write_stream<W: Write + Send + 'static>(mut writer: W) -> Self {
+    Self::alloc_context(
+      AVMem::new(IO_BUFFER_SIZE),
+      true,
+      vec![],
+      None,
+      Some(Box::new(move |_, buf| write_packet(&mut writer, buf))),
+      None,
+    )
+  }
+}
+
+/// Convert an [`io::Error`] to an FFmpeg error code by its kind, since
+/// the raw OS error isn't an errno on every platform.
+fn io_error_to_averror(err: &io::Error) -> i32 {
+  let errno = match err.kind() {
+    io::ErrorKind::UnexpectedEof => return ffi::AVERROR_EOF,
+    io::ErrorKind::InvalidData => return ffi::AVERROR_INVALIDDATA,
+    io::ErrorKind::OutOfMemory => return AVERROR_ENOMEM,
+    io::ErrorKind::NotFound => ffi::ENOENT,
+    io::ErrorKind::PermissionDenied => ffi::EACCES,
+    io::ErrorKind::ConnectionRefused => ffi::ECONNREFUSED,
+    io::ErrorKind::ConnectionReset => ffi::ECONNRESET,
+    io::ErrorKind::ConnectionAborted => ffi::ECONNABORTED,
+    io::ErrorKind::NotConnected => ffi::ENOTCONN,
+    io::ErrorKind::AddrInUse => ffi::EADDRINUSE,
+    io::ErrorKind::AddrNotAvailable => ffi::EADDRNOTAVAIL,
+    io::ErrorKind::BrokenPipe => ffi::EPIPE,
+    io::ErrorKind::AlreadyExists => ffi::EEXIST,
+    io::ErrorKind::WouldBlock => ffi::EAGAIN,
+    io::ErrorKind::InvalidInput => ffi::EINVAL,
+    io::ErrorKind::TimedOut => ffi::ETIMEDOUT,
+    io::ErrorKind::Interrupted => ffi::EINTR,
+    io::ErrorKind::Unsupported => ffi::ENOSYS,
+    _ => ffi::EIO,
+  };
+  ffi::AVERROR(errno)
+}
+
+fn read_packet(reader: &mut impl Read, buf: &mut [u8]) -> i32 {
+  loop {
+    match reader.read(buf) {
+      Ok(0) => return ffi::AVERROR_EOF,
+      Ok(len) => return len as i32,
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+      Err(e) => return io_error_to_averror(&e),
+    }
+  }
+}
+
+fn write_packet(writer: &mut impl Write, buf: &[u8]) -> i32 {
+  match writer.write_all(buf) {
+    Ok(()) => buf.len() as i32,
+    Err(e) => io_error_to_averror(&e),
+  }
+}
+
+fn seek(seeker: &mut impl Seek, offset: i64, whence: i32) -> i64 {
+  let whence = whence & !(ffi::AVSEEK_FORCE as i32);
+  let result = if whence & ffi::AVSEEK_SIZE as i32 != 0 {
+    // Report the stream size without moving the position.
+    seeker.stream_position().and_then(|current| {
+      let size = seeker.seek(SeekFrom::End(0))?;
+      seeker.seek(SeekFrom::Start(current))?;
+      Ok(size)
+    })
+  } else {
+    let pos = match whence as u32 {
+      ffi::SEEK_SET => match u64::try_from(offset) {
+        Ok(offset) => SeekFrom::Start(offset),
+        Err(_) => return ffi::AVERROR(ffi::EINVAL) as i64,
+      },
+      ffi::SEEK_CUR => SeekFrom::Current(offset),
+      ffi::SEEK_END => SeekFrom::End(offset),
+      _ => return ffi::AVERROR(ffi::EINVAL) as i64,
+    };
+    seeker.seek(pos)
+  };
+  match result {
+    Ok(pos) => pos as i64,
+    Err(e) => io_error_to_averror(&e) as i64,
+  }
+}
+
 impl Drop for AVIOContextCustom {
   fn drop(&mut self) {
+    // Write out pending output first, the callbacks are still alive here
+    // since `_opaque` is dropped after this function.
+    if self.write_flag != 0 {
+      unsafe { ffi::avio_flush(self.as_mut_ptr()) };
+    }
+
     // Recover the `AVMem` fom the buffer and drop it. We don't attach the
     // AVMem to this type because according to the documentation, the buffer
     // pointer may be changed during it's usage.
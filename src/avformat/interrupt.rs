@@ -0,0 +1,89 @@
+use std::{
+  os::raw::{c_int, c_void},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+  },
+  time::Instant,
+};
+
+use crate::ffi;
+
+pub type InterruptCallbackFn = Box<dyn FnMut() -> bool + Send + 'static>;
+
+/// A Rust closure installed as the [`ffi::AVIOInterruptCB`] of a format
+/// context or an IO context.
+///
+/// FFmpeg calls it repeatedly during blocking operations, e.g. opening an
+/// input, reading packets or writing the header. Once it returns `true`, the
+/// blocking function is aborted and
+/// [`RsmpegError::Interrupted`](crate::error::RsmpegError::Interrupted) is
+/// returned.
+///
+/// Clones share the same closure, each context holding a clone keeps it alive.
+#[derive(Clone)]
+pub struct InterruptCallback {
+  opaque: Arc<Mutex<InterruptCallbackFn>>,
+}
+
+impl InterruptCallback {
+  /// Create an [`InterruptCallback`] from a closure, return `true` from it to
+  /// abort the blocking operation.
+  pub fn new(callback: impl FnMut() -> bool + Send + 'static) -> Self {
+    let callback: InterruptCallbackFn = Box::new(callback);
+    Self {
+      opaque: Arc::new(Mutex::new(callback)),
+    }
+  }
+
+  /// Create an [`InterruptCallback`] which aborts once `token` is cancelled
+  /// or `deadline` has passed.
+  pub fn from_token(
+    token: CancellationToken,
+    deadline: Option<Instant>,
+  ) -> Self {
+    Self::new(move || {
+      token.is_cancelled()
+        || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    })
+  }
+
+  /// Get the raw [`ffi::AVIOInterruptCB`] calling this closure, it's valid as
+  /// long as `self` or one of its clones is alive.
+  pub fn as_raw(&self) -> ffi::AVIOInterruptCB {
+    unsafe extern "C" fn interrupt_c(opaque: *mut c_void) -> c_int {
+      let callback =
+        unsafe { (opaque as *const Mutex<InterruptCallbackFn>).as_ref() }
+          .unwrap();
+      (callback.lock().unwrap())() as c_int
+    }
+
+    ffi::AVIOInterruptCB {
+      callback: Some(interrupt_c),
+      opaque: Arc::as_ptr(&self.opaque) as *mut c_void,
+    }
+  }
+}
+
+/// A cloneable handle used to cancel blocking operations from another
+/// thread, see [`InterruptCallback::from_token`].
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Cancel the operations watching this token and its clones.
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+  }
+
+  /// Whether [`Self::cancel`] has been called on this token or its clones.
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+}
@@ -1,6 +1,8 @@
 //! Everything related to `libavformat`.
 mod avformat;
 mod avio;
+mod interrupt;
 
 pub use avformat::*;
 pub use avio::*;
+pub use interrupt::*;
@@ -1,7 +1,8 @@
 use crate::{
   avutil::{
-    av_image_fill_arrays, AVChannelLayoutRef, AVImage, AVMotionVector,
-    AVPixelFormat,
+    av_image_fill_arrays, AVChannelLayout, AVChannelLayoutRef, AVImage,
+    AVMotionVector, AVPixFmtDescriptorRef, AVPixelFormat, AVSampleFormat,
+    SampleType,
   },
   error::*,
   ffi,
@@ -181,6 +182,232 @@ impl AVFrame {
     }
   }
 
+  /// Get `(linesize, bytes per row, rows)` of plane `index` of a video
+  /// frame, `None` if there is no such plane or it's not accessible.
+  fn plane_layout(&self, index: usize) -> Option<(usize, usize, usize)> {
+    if index >= 4 || self.data[index].is_null() {
+      return None;
+    }
+    if self.width <= 0 || self.height <= 0 {
+      return None;
+    }
+    let desc = AVPixFmtDescriptorRef::get(self.format)?;
+    let flags = desc.flags as u32;
+    if flags & ffi::AV_PIX_FMT_FLAG_HWACCEL != 0 {
+      return None;
+    }
+    // The palette of paletted formats: 256 32-bit entries.
+    if index == 1 && flags & ffi::AV_PIX_FMT_FLAG_PAL != 0 {
+      return Some((1024, 1024, 1));
+    }
+    // Negative linesizes (bottom-up images) aren't supported.
+    let linesize = usize::try_from(self.linesize[index]).ok()?;
+    let width = unsafe {
+      ffi::av_image_get_linesize(self.format, self.width, index as i32)
+    };
+    let width = usize::try_from(width).ok().filter(|&x| x > 0)?;
+    if width > linesize {
+      return None;
+    }
+    // Only the chroma planes are subsampled, not the alpha one.
+    let height = if index == 1 || index == 2 {
+      let shift = desc.log2_chroma_h;
+      (self.height + (1 << shift) - 1) >> shift
+    } else {
+      self.height
+    };
+    Some((linesize, width, height as usize))
+  }
+
+  /// Get the bytes of plane `index` of a video frame, from the start of the
+  /// first row to the end of the last one, including the padding between
+  /// rows.
+  ///
+  /// Return `None` if there is no such plane, for hardware frames and for
+  /// bottom-up images with negative linesizes.
+  pub fn plane(&self, index: usize) -> Option<&[u8]> {
+    let (linesize, width, height) = self.plane_layout(index)?;
+    let len = linesize * (height - 1) + width;
+    Some(unsafe { slice::from_raw_parts(self.data[index], len) })
+  }
+
+  /// Mutable version of [`Self::plane`], also `None` if the frame isn't
+  /// writable, see [`Self::make_writable`].
+  pub fn plane_mut(&mut self, index: usize) -> Option<&mut [u8]> {
+    let (linesize, width, height) = self.plane_layout(index)?;
+    if !self.is_writable().ok()? {
+      return None;
+    }
+    let len = linesize * (height - 1) + width;
+    Some(unsafe { slice::from_raw_parts_mut(self.data[index], len) })
+  }
+
+  /// Iterate over the rows of plane `index` of a video frame, without
+  /// padding. The width and height of each plane are computed from the
+  /// pixel format, e.g. half of the frame's for the chroma planes of
+  /// `yuv420p`.
+  pub fn rows(&self, index: usize) -> Option<impl Iterator<Item = &[u8]>> {
+    let (linesize, width, _) = self.plane_layout(index)?;
+    let plane = self.plane(index)?;
+    Some(plane.chunks(linesize).map(move |row| &row[..width]))
+  }
+
+  /// Copy the rows of all the planes of a video frame into a tightly packed
+  /// buffer, i.e. [`AVImage`] with `align` of 1.
+  pub fn to_packed_vec(&self) -> Option<Vec<u8>> {
+    let mut buffer = vec![];
+    for index in 0..4 {
+      match self.rows(index) {
+        Some(rows) => rows.for_each(|row| buffer.extend_from_slice(row)),
+        None if index == 0 => return None,
+        None => break,
+      }
+    }
+    Some(buffer)
+  }
+
+  /// Create an audio frame of packed samples of `T` from the interleaved
+  /// `samples`, whose length should be a multiple of the channels of
+  /// `ch_layout`.
+  pub fn from_samples<T: SampleType>(
+    samples: &[T],
+    ch_layout: &AVChannelLayout,
+    sample_rate: i32,
+  ) -> Result<Self> {
+    let channels = usize::try_from(ch_layout.nb_channels)?;
+    if channels == 0 || samples.len() % channels != 0 {
+      return Err(RsmpegError::AVFrameInvalidAllocatingError(ffi::AVERROR(
+        ffi::EINVAL,
+      )));
+    }
+    let mut frame = Self::new_audio(
+      T::PACKED_FORMAT,
+      samples.len() / channels,
+      ch_layout,
+      sample_rate,
+    )?;
+    // unwrap: the buffer is just allocated.
+    frame.samples_mut::<T>().unwrap().copy_from_slice(samples);
+    Ok(frame)
+  }
+
+  /// Create an audio frame of planar samples of `T` from one slice per
+  /// channel of `ch_layout`, all of the same length.
+  pub fn from_planes<T: SampleType>(
+    planes: &[&[T]],
+    ch_layout: &AVChannelLayout,
+    sample_rate: i32,
+  ) -> Result<Self> {
+    let nb_samples = planes.first().map_or(0, |plane| plane.len());
+    if usize::try_from(ch_layout.nb_channels)? != planes.len()
+      || planes.iter().any(|plane| plane.len() != nb_samples)
+    {
+      return Err(RsmpegError::AVFrameInvalidAllocatingError(ffi::AVERROR(
+        ffi::EINVAL,
+      )));
+    }
+    let mut frame =
+      Self::new_audio(T::PLANAR_FORMAT, nb_samples, ch_layout, sample_rate)?;
+    // unwrap: the buffers are just allocated.
+    for (dst, src) in frame.planes_mut::<T>().unwrap().into_iter().zip(planes) {
+      dst.copy_from_slice(src);
+    }
+    Ok(frame)
+  }
+
+  fn new_audio(
+    sample_fmt: AVSampleFormat,
+    nb_samples: usize,
+    ch_layout: &AVChannelLayout,
+    sample_rate: i32,
+  ) -> Result<Self> {
+    let mut frame = Self::new();
+    frame.set_format(sample_fmt);
+    frame.set_nb_samples(nb_samples.try_into()?);
+    frame.set_ch_layout(ch_layout.clone().into_inner());
+    frame.set_sample_rate(sample_rate);
+    frame.alloc_buffer()?;
+    Ok(frame)
+  }
+
+  /// Get `(nb_samples, channels)` of an audio frame in `sample_fmt`.
+  fn audio_layout(&self, sample_fmt: AVSampleFormat) -> Option<(usize, usize)> {
+    if self.format != sample_fmt || self.extended_data.is_null() {
+      return None;
+    }
+    let nb_samples = usize::try_from(self.nb_samples).ok()?;
+    // Video frames have no channels.
+    let channels = usize::try_from(self.ch_layout.nb_channels)
+      .ok()
+      .filter(|&x| x > 0)?;
+    Some((nb_samples, channels))
+  }
+
+  /// Get the interleaved samples of an audio frame, `nb_samples * channels`
+  /// of them.
+  ///
+  /// Return `None` if the sample format of the frame isn't the packed one of
+  /// `T`, e.g. `AV_SAMPLE_FMT_FLT` for `f32`.
+  pub fn samples<T: SampleType>(&self) -> Option<&[T]> {
+    let (nb_samples, channels) = self.audio_layout(T::PACKED_FORMAT)?;
+    let data = self.data[0].upgrade()?;
+    Some(unsafe {
+      slice::from_raw_parts(data.as_ptr() as *const T, nb_samples * channels)
+    })
+  }
+
+  /// Mutable version of [`Self::samples`], also `None` if the frame isn't
+  /// writable, see [`Self::make_writable`].
+  pub fn samples_mut<T: SampleType>(&mut self) -> Option<&mut [T]> {
+    let (nb_samples, channels) = self.audio_layout(T::PACKED_FORMAT)?;
+    if !self.is_writable().ok()? {
+      return None;
+    }
+    let data = self.data[0].upgrade()?;
+    Some(unsafe {
+      slice::from_raw_parts_mut(data.as_ptr() as *mut T, nb_samples * channels)
+    })
+  }
+
+  /// Get the samples of each channel of an audio frame, `nb_samples` of
+  /// them per channel.
+  ///
+  /// Return `None` if the sample format of the frame isn't the planar one of
+  /// `T`, e.g. `AV_SAMPLE_FMT_FLTP` for `f32`.
+  pub fn planes<T: SampleType>(&self) -> Option<Vec<&[T]>> {
+    let (nb_samples, channels) = self.audio_layout(T::PLANAR_FORMAT)?;
+    // More than 8 channels are only in `extended_data`.
+    let planes = unsafe { slice::from_raw_parts(self.extended_data, channels) };
+    planes
+      .iter()
+      .map(|plane| {
+        let plane = plane.upgrade()?;
+        Some(unsafe {
+          slice::from_raw_parts(plane.as_ptr() as *const T, nb_samples)
+        })
+      })
+      .collect()
+  }
+
+  /// Mutable version of [`Self::planes`], also `None` if the frame isn't
+  /// writable, see [`Self::make_writable`].
+  pub fn planes_mut<T: SampleType>(&mut self) -> Option<Vec<&mut [T]>> {
+    let (nb_samples, channels) = self.audio_layout(T::PLANAR_FORMAT)?;
+    if !self.is_writable().ok()? {
+      return None;
+    }
+    let planes = unsafe { slice::from_raw_parts(self.extended_data, channels) };
+    planes
+      .iter()
+      .map(|plane| {
+        let plane = plane.upgrade()?;
+        Some(unsafe {
+          slice::from_raw_parts_mut(plane.as_ptr() as *mut T, nb_samples)
+        })
+      })
+      .collect()
+  }
+
   /// Unreference all the buffers referenced by frame and reset the frame fields.
   pub fn unref(&mut self) {
     unsafe {
@@ -328,6 +555,88 @@ mod test {
     ));
   }
 
+  #[test]
+  fn test_frame_planes() {
+    let mut frame = AVFrame::new();
+    frame.set_format(ffi::AV_PIX_FMT_YUV420P);
+    frame.set_width(5);
+    frame.set_height(3);
+    frame.alloc_buffer().unwrap();
+
+    for (index, value) in [(0, 1), (1, 2), (2, 3)] {
+      frame.plane_mut(index).unwrap().fill(value);
+    }
+    assert!(frame.plane(3).is_none());
+    assert!(frame.plane_mut(3).is_none());
+
+    let rows: Vec<_> = frame.rows(0).unwrap().collect();
+    assert_eq!(rows, [[1; 5]; 3]);
+    let rows: Vec<_> = frame.rows(1).unwrap().collect();
+    assert_eq!(rows, [[2; 3]; 2]);
+
+    let packed = frame.to_packed_vec().unwrap();
+    let size =
+      AVImage::get_buffer_size(ffi::AV_PIX_FMT_YUV420P, 5, 3, 1).unwrap();
+    assert_eq!(packed.len(), size as usize);
+    assert_eq!(packed, [&[1; 15][..], &[2; 6], &[3; 6]].concat());
+
+    // Shared buffers aren't writable.
+    let clone = frame.clone();
+    assert!(frame.plane_mut(0).is_none());
+    assert_eq!(clone.plane(0), frame.plane(0));
+  }
+
+  #[test]
+  fn test_frame_packed_plane() {
+    let mut frame = AVFrame::new();
+    frame.set_format(ffi::AV_PIX_FMT_RGB24);
+    frame.set_width(3);
+    frame.set_height(2);
+    frame.alloc_buffer().unwrap();
+    assert!(frame.rows(0).unwrap().all(|row| row.len() == 9));
+    assert!(frame.plane(1).is_none());
+    assert_eq!(frame.to_packed_vec().unwrap().len(), 18);
+
+    // Not a video frame.
+    assert!(AVFrame::new().to_packed_vec().is_none());
+  }
+
+  #[test]
+  fn test_frame_samples() {
+    let stereo = AVChannelLayout::from_nb_channels(2);
+    let samples = [0.0, 0.5, -0.25, 1.0, 0.75, -1.0];
+    let frame = AVFrame::from_samples(&samples, &stereo, 44100).unwrap();
+    assert_eq!(frame.nb_samples, 3);
+    assert_eq!(frame.sample_rate, 44100);
+    assert_eq!(frame.format, ffi::AV_SAMPLE_FMT_FLT);
+    assert_eq!(frame.samples::<f32>().unwrap(), samples);
+    assert!(frame.samples::<i16>().is_none());
+    assert!(frame.planes::<f32>().is_none());
+
+    assert!(AVFrame::from_samples(&[0i16; 5], &stereo, 44100).is_err());
+  }
+
+  #[test]
+  fn test_frame_planes_audio() {
+    let stereo = AVChannelLayout::from_nb_channels(2);
+    let left = [1i16, 2, 3, 4];
+    let right = [-1i16, -2, -3, -4];
+    let mut frame =
+      AVFrame::from_planes(&[&left[..], &right], &stereo, 48000).unwrap();
+    assert_eq!(frame.format, ffi::AV_SAMPLE_FMT_S16P);
+    assert_eq!(frame.planes::<i16>().unwrap(), [left, right]);
+    assert!(frame.samples::<i16>().is_none());
+    assert!(frame.planes::<f32>().is_none());
+
+    frame.planes_mut::<i16>().unwrap()[1].fill(0);
+    assert_eq!(frame.planes::<i16>().unwrap()[1], [0; 4]);
+
+    assert!(AVFrame::from_planes(&[&left[..]], &stereo, 48000).is_err());
+    assert!(
+      AVFrame::from_planes(&[&left[..], &right[1..]], &stereo, 48000).is_err()
+    );
+  }
+
   #[test]
   fn test_frame_with_image_buffer() {
     let image = AVImage::new(ffi::AV_PIX_FMT_RGB24, 256, 256, 0).unwrap();
@@ -5,11 +5,11 @@ const AV_NUM_DATA_POINTERS: usize = ffi::AV_NUM_DATA_POINTERS as usize;
 
 wrap! {
     AVImage: Vec<u8>,
-    data: [*mut u8; AV_NUM_DATA_POINTERS] = [ptr::null_mut(); AV_NUM_DATA_POINTERS],
-    linesizes: [i32; AV_NUM_DATA_POINTERS] = [0; AV_NUM_DATA_POINTERS],
-    width: i32 = 0,
-    height: i32 = 0,
-    pix_fmt: AVPixelFormat = ffi::AV_PIX_FMT_NONE,
+    pub data: [*mut u8; AV_NUM_DATA_POINTERS] = [ptr::null_mut(); AV_NUM_DATA_POINTERS],
+    pub linesizes: [i32; AV_NUM_DATA_POINTERS] = [0; AV_NUM_DATA_POINTERS],
+    pub width: i32 = 0,
+    pub height: i32 = 0,
+    pub pix_fmt: AVPixelFormat = ffi::AV_PIX_FMT_NONE,
 }
 
 impl AVImage {
@@ -0,0 +1,414 @@
+use crate::{
+  error::{Result, RsmpegError},
+  ffi,
+  shared::PointerUpgrade,
+};
+use std::{
+  cell::RefCell,
+  collections::VecDeque,
+  ffi::{c_char, c_int, c_void, CStr},
+  marker::PhantomData,
+  sync::{Once, RwLock},
+};
+
+/// Level of the FFmpeg log messages, the `AV_LOG_*` constants, from the
+/// least to the most verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+  /// Print no output.
+  Quiet,
+  /// Something went really wrong and we will crash now.
+  Panic,
+  /// Something went wrong and recovery is not possible.
+  Fatal,
+  /// Something went wrong and cannot losslessly be recovered.
+  Error,
+  /// Something somehow does not look correct.
+  Warning,
+  /// Standard information.
+  Info,
+  /// Detailed information.
+  Verbose,
+  /// Stuff which is only useful for libav* developers.
+  Debug,
+  /// Extremely verbose debugging, useful for libav* development.
+  Trace,
+}
+
+impl LogLevel {
+  /// Get the level of a raw `AV_LOG_*` value, values between two levels
+  /// belong to the less verbose one.
+  pub fn from_raw(level: c_int) -> Self {
+    match level {
+      c_int::MIN..=-1 => Self::Quiet,
+      0..=7 => Self::Panic,
+      8..=15 => Self::Fatal,
+      16..=23 => Self::Error,
+      24..=31 => Self::Warning,
+      32..=39 => Self::Info,
+      40..=47 => Self::Verbose,
+      48..=55 => Self::Debug,
+      _ => Self::Trace,
+    }
+  }
+
+  /// Get the raw `AV_LOG_*` value of the level.
+  pub fn as_raw(self) -> c_int {
+    (match self {
+      Self::Quiet => return ffi::AV_LOG_QUIET,
+      Self::Panic => ffi::AV_LOG_PANIC,
+      Self::Fatal => ffi::AV_LOG_FATAL,
+      Self::Error => ffi::AV_LOG_ERROR,
+      Self::Warning => ffi::AV_LOG_WARNING,
+      Self::Info => ffi::AV_LOG_INFO,
+      Self::Verbose => ffi::AV_LOG_VERBOSE,
+      Self::Debug => ffi::AV_LOG_DEBUG,
+      Self::Trace => ffi::AV_LOG_TRACE,
+    }) as c_int
+  }
+}
+
+/// Set the global log level, messages more verbose than it are dropped
+/// before being formatted.
+pub fn log_set_level(level: LogLevel) {
+  unsafe { ffi::av_log_set_level(level.as_raw()) }
+}
+
+/// Get the global log level.
+pub fn log_get_level() -> LogLevel {
+  LogLevel::from_raw(unsafe { ffi::av_log_get_level() })
+}
+
+/// An FFmpeg log callback, see `av_log_set_callback`.
+pub type LogCallback =
+  unsafe extern "C" fn(*mut c_void, c_int, *const c_char, ffi::va_list);
+
+/// Where the messages go besides the [`LogCapture`]s.
+#[derive(Clone, Copy)]
+enum Sink {
+  Callback(LogCallback),
+  /// Forwarded to `log` or `tracing`.
+  #[cfg_attr(not(any(feature = "log", feature = "tracing")), allow(dead_code))]
+  Bridge,
+}
+
+static SINK: RwLock<Sink> =
+  RwLock::new(Sink::Callback(ffi::av_log_default_callback));
+
+static INSTALL: Once = Once::new();
+
+/// Route the messages to `sink`, installing the log callback of this crate
+/// with `av_log_set_callback` on the first call.
+fn set_sink(sink: Sink) {
+  *SINK.write().unwrap() = sink;
+  install_callback();
+}
+
+fn install_callback() {
+  INSTALL.call_once(|| unsafe { ffi::av_log_set_callback(Some(log_callback)) });
+}
+
+/// Pass the FFmpeg log messages to `callback`, like `av_log_set_callback`,
+/// but keeping the [`LogCapture`]s working.
+///
+/// This also stops [`log_set_bridge`].
+pub fn log_set_callback(callback: LogCallback) {
+  set_sink(Sink::Callback(callback));
+}
+
+/// Restore the default log callback of FFmpeg, which prints to stderr.
+///
+/// This also stops [`log_set_bridge`], the alive [`LogCapture`]s keep
+/// capturing.
+pub fn log_set_default_callback() {
+  log_set_callback(ffi::av_log_default_callback);
+}
+
+/// Forward the FFmpeg log messages to the `log` crate, or to `tracing` if
+/// the `tracing` feature is enabled, instead of printing them to stderr.
+///
+/// Messages are filtered with [`log_set_level`] first.
+///
+/// With `log`, the target of a record is `ffmpeg::<name>`, where `<name>` is
+/// the name of the emitting object, e.g. `ffmpeg::h264` for a decoder or
+/// `ffmpeg::mov,mp4,m4a,3gp,3g2,mj2` for a demuxer, and just `ffmpeg` for
+/// messages without an object. With `tracing`, the target of the events is
+/// `ffmpeg` and the name is in the `class` field.
+///
+/// FFmpeg levels map to `Error` up to [`LogLevel::Error`], then `Warn`,
+/// `Info`, `Debug` for [`LogLevel::Verbose`] and [`LogLevel::Debug`], and
+/// `Trace`.
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub fn log_set_bridge() {
+  set_sink(Sink::Bridge);
+}
+
+/// Capture the FFmpeg log lines of the current thread while it's alive.
+///
+/// Errors returned meanwhile by calls such as
+/// [`AVFormatContextInput::open`](crate::avformat::AVFormatContextInput::open),
+/// [`AVCodecContext::open`](crate::avcodec::AVCodecContext::open) or
+/// [`AVFilterGraph::parse_ptr`](crate::avfilter::AVFilterGraph::parse_ptr)
+/// then carry the lines logged during the failed call, see
+/// [`RsmpegError::log_lines`].
+///
+/// Messages are still passed to the callback set by [`log_set_callback`],
+/// which prints them to stderr by default, or forwarded by
+/// [`log_set_bridge`].
+///
+/// The first capture installs the log callback of this crate process-wide
+/// with `av_log_set_callback`, as [`log_set_callback`],
+/// [`log_set_default_callback`] and [`log_set_bridge`] do. A callback set
+/// before with `ffi::av_log_set_callback` is replaced, set it with
+/// [`log_set_callback`] instead to keep it. A callback set afterwards with
+/// `ffi::av_log_set_callback` stops the captures.
+pub struct LogCapture {
+  previous: Option<Capture>,
+  // The capture belongs to the current thread.
+  _marker: PhantomData<*const ()>,
+}
+
+impl LogCapture {
+  /// Start capturing, keeping the last `limit` lines.
+  pub fn new(limit: usize) -> Self {
+    install_callback();
+    let previous = LOG_CAPTURE.with(|capture| {
+      capture.replace(Some(Capture {
+        limit,
+        lines: VecDeque::with_capacity(limit),
+        count: 0,
+      }))
+    });
+    Self {
+      previous,
+      _marker: PhantomData,
+    }
+  }
+}
+
+impl Drop for LogCapture {
+  fn drop(&mut self) {
+    LOG_CAPTURE.with(|capture| *capture.borrow_mut() = self.previous.take());
+  }
+}
+
+/// Last log lines of a thread.
+struct Capture {
+  limit: usize,
+  lines: VecDeque<String>,
+  /// Number of lines pushed since the start.
+  count: usize,
+}
+
+impl Capture {
+  fn push(&mut self, line: String) {
+    if self.limit == 0 {
+      return;
+    }
+    if self.lines.len() == self.limit {
+      self.lines.pop_front();
+    }
+    self.lines.push_back(line);
+    self.count += 1;
+  }
+
+  /// Get the kept lines pushed after `count` lines.
+  fn lines_since(&self, count: usize) -> Vec<String> {
+    let len = self.count.saturating_sub(count).min(self.lines.len());
+    self
+      .lines
+      .range(self.lines.len() - len..)
+      .cloned()
+      .collect()
+  }
+}
+
+thread_local! {
+  static LOG_CAPTURE: RefCell<Option<Capture>> = const { RefCell::new(None) };
+
+  /// Message of the current thread which isn't terminated by a newline yet,
+  /// FFmpeg sometimes builds a line with several calls.
+  static PARTIAL_LINE: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Run `f`, attaching the log lines captured by a [`LogCapture`] of the
+/// current thread during the call to its error.
+pub(crate) fn capture_log<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+  let count =
+    LOG_CAPTURE.with(|capture| capture.borrow().as_ref().map(|x| x.count));
+  f().map_err(|error| {
+    let (Some(count), false) =
+      (count, matches!(error, RsmpegError::Logged(..)))
+    else {
+      return error;
+    };
+    let lines = LOG_CAPTURE.with(|capture| {
+      capture
+        .borrow()
+        .as_ref()
+        .map(|x| x.lines_since(count))
+        .unwrap_or_default()
+    });
+    if lines.is_empty() {
+      error
+    } else {
+      RsmpegError::Logged(Box::new(error), lines)
+    }
+  })
+}
+
+unsafe extern "C" fn log_callback(
+  avcl: *mut c_void,
+  level: c_int,
+  fmt: *const c_char,
+  vl: ffi::va_list,
+) {
+  let sink = *SINK.read().unwrap();
+  let capturing = LOG_CAPTURE.with(|capture| capture.borrow().is_some());
+  if (capturing || matches!(sink, Sink::Bridge))
+    && level <= unsafe { ffi::av_log_get_level() }
+  {
+    if let Some(message) = unsafe { format_line(avcl, level, fmt, vl) } {
+      let name = unsafe { item_name(avcl) };
+      LOG_CAPTURE.with(|capture| {
+        if let Some(capture) = capture.borrow_mut().as_mut() {
+          capture.push(match &name {
+            Some(name) => format!("[{name}] {message}"),
+            None => message.clone(),
+          });
+        }
+      });
+      #[cfg(any(feature = "log", feature = "tracing"))]
+      if matches!(sink, Sink::Bridge) {
+        emit(LogLevel::from_raw(level), name.as_deref(), &message);
+      }
+    }
+  }
+  // `av_log_format_line2` formats a copy of `vl`, so the callback gets the
+  // message untouched and prints it as usual.
+  if let Sink::Callback(callback) = sink {
+    unsafe { callback(avcl, level, fmt, vl) }
+  }
+}
+
+#[cfg(feature = "tracing")]
+fn emit(level: LogLevel, name: Option<&str>, message: &str) {
+  let class = name.unwrap_or_default();
+  match level {
+    LogLevel::Quiet => {}
+    LogLevel::Panic | LogLevel::Fatal | LogLevel::Error => {
+      tracing::error!(target: "ffmpeg", class, "{message}")
+    }
+    LogLevel::Warning => tracing::warn!(target: "ffmpeg", class, "{message}"),
+    LogLevel::Info => tracing::info!(target: "ffmpeg", class, "{message}"),
+    LogLevel::Verbose | LogLevel::Debug => {
+      tracing::debug!(target: "ffmpeg", class, "{message}")
+    }
+    LogLevel::Trace => tracing::trace!(target: "ffmpeg", class, "{message}"),
+  }
+}
+
+#[cfg(all(feature = "log", not(feature = "tracing")))]
+fn emit(level: LogLevel, name: Option<&str>, message: &str) {
+  let level = match level {
+    LogLevel::Quiet => return,
+    LogLevel::Panic | LogLevel::Fatal | LogLevel::Error => ::log::Level::Error,
+    LogLevel::Warning => ::log::Level::Warn,
+    LogLevel::Info => ::log::Level::Info,
+    LogLevel::Verbose | LogLevel::Debug => ::log::Level::Debug,
+    LogLevel::Trace => ::log::Level::Trace,
+  };
+  let target = match name {
+    Some(name) => format!("ffmpeg::{name}"),
+    None => "ffmpeg".to_string(),
+  };
+  ::log::log!(target: &target, level, "{message}");
+}
+
+/// Format a message without the `[name @ 0x...]` prefix, return the whole
+/// line once it's terminated.
+///
+/// # Safety
+///
+/// The arguments should be the ones of an `av_log` callback.
+unsafe fn format_line(
+  avcl: *mut c_void,
+  level: c_int,
+  fmt: *const c_char,
+  vl: ffi::va_list,
+) -> Option<String> {
+  let mut line = [0 as c_char; 1024];
+  let mut print_prefix = 0;
+  let len = unsafe {
+    ffi::av_log_format_line2(
+      avcl,
+      level,
+      fmt,
+      vl,
+      line.as_mut_ptr(),
+      line.len() as c_int,
+      &mut print_prefix,
+    )
+  };
+  let part = unsafe { CStr::from_ptr(line.as_ptr()) }.to_string_lossy();
+  // `vl` can't be formatted again, so a truncated message is taken as a
+  // whole line, otherwise it would be merged into the next message.
+  let truncated = len < 0 || len as usize >= line.len();
+  PARTIAL_LINE.with(|partial| {
+    let mut partial = partial.borrow_mut();
+    partial.push_str(&part);
+    if !truncated && !partial.ends_with('\n') {
+      return None;
+    }
+    let message = partial.trim_end().to_string();
+    partial.clear();
+    (!message.is_empty()).then_some(message)
+  })
+}
+
+/// Get the name of the object which emits a message, e.g. the codec name
+/// of an `AVCodecContext`.
+///
+/// # Safety
+///
+/// `avcl` should be null or point to a struct whose first element is a
+/// pointer to an AVClass.
+unsafe fn item_name(avcl: *mut c_void) -> Option<String> {
+  let avcl = avcl.upgrade()?;
+  let class = unsafe { *(avcl.as_ptr() as *const *const ffi::AVClass) };
+  let class = unsafe { class.upgrade()?.as_ref() };
+  let name = match class.item_name {
+    Some(item_name) => unsafe { item_name(avcl.as_ptr()) },
+    None => class.class_name,
+  };
+  let name = name.upgrade()?;
+  Some(
+    unsafe { CStr::from_ptr(name.as_ptr()) }
+      .to_string_lossy()
+      .into_owned(),
+  )
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_log_level() {
+    for level in [
+      LogLevel::Quiet,
+      LogLevel::Panic,
+      LogLevel::Fatal,
+      LogLevel::Error,
+      LogLevel::Warning,
+      LogLevel::Info,
+      LogLevel::Verbose,
+      LogLevel::Debug,
+      LogLevel::Trace,
+    ] {
+      assert_eq!(LogLevel::from_raw(level.as_raw()), level);
+    }
+    assert_eq!(LogLevel::from_raw(20), LogLevel::Error);
+    assert!(LogLevel::Warning < LogLevel::Info);
+  }
+}
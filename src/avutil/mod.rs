@@ -6,6 +6,7 @@ mod error;
 mod file;
 mod frame;
 mod imgutils;
+mod log;
 mod media_type;
 mod mem;
 mod motion_vector;
@@ -24,6 +25,7 @@ pub use error::*;
 pub use file::*;
 pub use frame::*;
 pub use imgutils::*;
+pub use log::*;
 pub use media_type::*;
 pub use mem::*;
 pub use motion_vector::*;
@@ -1,10 +1,13 @@
 use crate::{
-  error::Result,
+  error::{Result, RsmpegError},
   ffi,
   ffi::{AVPixelFormat, AVRational, AVSampleFormat},
-  shared::RetUpgrade,
+  shared::{string_from_ptr, PointerUpgrade, RetUpgrade},
+};
+use std::{
+  ffi::{c_char, c_double, c_int, c_void, CStr, CString},
+  ptr,
 };
-use std::ffi::{c_double, c_int, c_void, CStr};
 
 /// - `name`: the name of the field to set
 /// - `val`: if the field is not of a string type, then the given string is parsed.
@@ -163,3 +166,519 @@ pub unsafe fn opt_set_video_rate(
     .upgrade()?;
   Ok(())
 }
+
+/// FFmpeg objects whose settings are reachable with the AVOption API, i.e.
+/// structs whose first field is an `AVClass` pointer.
+///
+/// Options of the children, e.g. the private options of the codec of an
+/// [`AVCodecContext`](crate::avcodec::AVCodecContext) or of the muxer of an
+/// [`AVFormatContextOutput`](crate::avformat::AVFormatContextOutput), are
+/// included in [`Self::options`] and can be get and set by name too.
+///
+/// # Safety
+///
+/// [`Self::as_opt_ptr`] must return a pointer to a struct whose first
+/// element is a pointer to an `AVClass`, valid as long as `self`.
+pub unsafe trait AVOptions {
+  /// Get the pointer to the object with the `AVClass`.
+  fn as_opt_ptr(&self) -> *mut c_void;
+
+  /// Whether the object is initialized. Most options are only read on
+  /// initialization, so afterwards only the ones with
+  /// [`OptionFlags::RUNTIME_PARAM`] can be set.
+  fn is_initialized(&self) -> bool {
+    false
+  }
+
+  /// List the options of the object and of its children.
+  fn options(&self) -> Vec<AVOptionInfo> {
+    let mut options = vec![];
+    unsafe { collect_options(self.as_opt_ptr(), &mut options) };
+    options
+  }
+
+  /// Get the value of the option `name` as a string.
+  fn get_opt(&self, name: &CStr) -> Result<CString> {
+    let mut value = ptr::null_mut();
+    opt_ret(
+      unsafe {
+        ffi::av_opt_get(self.as_opt_ptr(), name.as_ptr(), SEARCH, &mut value)
+      },
+      name,
+    )?;
+    // Only fails on no memory, so unwrap().
+    let value = value.upgrade().unwrap();
+    let string = unsafe { CStr::from_ptr(value.as_ptr() as *const c_char) };
+    let string = string.to_owned();
+    unsafe { ffi::av_free(value.as_ptr().cast()) };
+    Ok(string)
+  }
+
+  /// Get the value of the integer option `name`, e.g. int, flags or bool
+  /// options.
+  fn get_opt_int(&self, name: &CStr) -> Result<i64> {
+    let mut value = 0;
+    opt_ret(
+      unsafe {
+        ffi::av_opt_get_int(
+          self.as_opt_ptr(),
+          name.as_ptr(),
+          SEARCH,
+          &mut value,
+        )
+      },
+      name,
+    )?;
+    Ok(value)
+  }
+
+  /// Get the value of the floating point option `name`.
+  fn get_opt_double(&self, name: &CStr) -> Result<f64> {
+    let mut value = 0.;
+    opt_ret(
+      unsafe {
+        ffi::av_opt_get_double(
+          self.as_opt_ptr(),
+          name.as_ptr(),
+          SEARCH,
+          &mut value,
+        )
+      },
+      name,
+    )?;
+    Ok(value)
+  }
+
+  /// Get the value of the rational option `name`.
+  fn get_opt_q(&self, name: &CStr) -> Result<AVRational> {
+    let mut value = AVRational { num: 0, den: 1 };
+    opt_ret(
+      unsafe {
+        ffi::av_opt_get_q(self.as_opt_ptr(), name.as_ptr(), SEARCH, &mut value)
+      },
+      name,
+    )?;
+    Ok(value)
+  }
+
+  /// Set the option `name` from a string, which is parsed according to the
+  /// type of the option, see [`opt_set`].
+  fn set_opt(&mut self, name: &CStr, value: &CStr) -> Result<()> {
+    check_settable(self, name)?;
+    opt_ret(
+      unsafe {
+        ffi::av_opt_set(
+          self.as_opt_ptr(),
+          name.as_ptr(),
+          value.as_ptr(),
+          SEARCH,
+        )
+      },
+      name,
+    )
+  }
+
+  /// Set the integer option `name`.
+  fn set_opt_int(&mut self, name: &CStr, value: i64) -> Result<()> {
+    check_settable(self, name)?;
+    opt_ret(
+      unsafe {
+        ffi::av_opt_set_int(self.as_opt_ptr(), name.as_ptr(), value, SEARCH)
+      },
+      name,
+    )
+  }
+
+  /// Set the floating point option `name`.
+  fn set_opt_double(&mut self, name: &CStr, value: f64) -> Result<()> {
+    check_settable(self, name)?;
+    opt_ret(
+      unsafe {
+        ffi::av_opt_set_double(self.as_opt_ptr(), name.as_ptr(), value, SEARCH)
+      },
+      name,
+    )
+  }
+
+  /// Set the rational option `name`.
+  fn set_opt_q(&mut self, name: &CStr, value: AVRational) -> Result<()> {
+    check_settable(self, name)?;
+    opt_ret(
+      unsafe {
+        ffi::av_opt_set_q(self.as_opt_ptr(), name.as_ptr(), value, SEARCH)
+      },
+      name,
+    )
+  }
+}
+
+const SEARCH: c_int = ffi::AV_OPT_SEARCH_CHILDREN as c_int;
+
+/// Reject the options of an initialized object which can't be changed at
+/// runtime, see [`AVOptions::is_initialized`].
+fn check_settable<T: AVOptions + ?Sized>(
+  object: &T,
+  name: &CStr,
+) -> Result<()> {
+  if !object.is_initialized() {
+    return Ok(());
+  }
+  let option = unsafe {
+    ffi::av_opt_find2(
+      object.as_opt_ptr(),
+      name.as_ptr(),
+      ptr::null(),
+      0,
+      SEARCH,
+      ptr::null_mut(),
+    )
+  };
+  let name = || name.to_string_lossy().into_owned();
+  match option.upgrade() {
+    None => Err(RsmpegError::OptionNotFound(name())),
+    Some(option)
+      if unsafe { option.as_ref() }.flags
+        & ffi::AV_OPT_FLAG_RUNTIME_PARAM as c_int
+        == 0 =>
+    {
+      Err(RsmpegError::OptionNotRuntime(name()))
+    }
+    Some(_) => Ok(()),
+  }
+}
+
+/// Map `AVERROR_OPTION_NOT_FOUND` to [`RsmpegError::OptionNotFound`].
+fn opt_ret(ret: c_int, name: &CStr) -> Result<()> {
+  match ret.upgrade() {
+    Ok(_) => Ok(()),
+    Err(ffi::AVERROR_OPTION_NOT_FOUND) => Err(RsmpegError::OptionNotFound(
+      name.to_string_lossy().into_owned(),
+    )),
+    Err(err) => Err(err.into()),
+  }
+}
+
+/// Collect the options of `obj` and, recursively, of its children.
+///
+/// # Safety
+///
+/// `obj` should points to a struct whose first element is a pointer to an
+/// AVClass.
+unsafe fn collect_options(obj: *mut c_void, options: &mut Vec<AVOptionInfo>) {
+  let class = unsafe { *(obj as *const *const ffi::AVClass) };
+  let Some(class) = class.upgrade() else {
+    return;
+  };
+  let class_name = string_from_ptr(unsafe { class.as_ref() }.class_name);
+
+  let mut raw_options = vec![];
+  let mut option = ptr::null();
+  loop {
+    option = unsafe { ffi::av_opt_next(obj, option) };
+    let Some(raw) = option.upgrade() else {
+      break;
+    };
+    raw_options.push(unsafe { raw.as_ref() });
+  }
+
+  for option in &raw_options {
+    if option.type_ == ffi::AV_OPT_TYPE_CONST {
+      continue;
+    }
+    let unit = string_from_ptr(option.unit);
+    let constants = match &unit {
+      Some(unit) => raw_options
+        .iter()
+        .filter(|x| {
+          x.type_ == ffi::AV_OPT_TYPE_CONST
+            && string_from_ptr(x.unit).as_ref() == Some(unit)
+        })
+        .map(|x| OptionConstant {
+          name: string_from_ptr(x.name).unwrap_or_default(),
+          help: string_from_ptr(x.help),
+          value: unsafe { x.default_val.i64_ },
+        })
+        .collect(),
+      None => vec![],
+    };
+    let option_type = OptionType::from_raw(option.type_);
+    options.push(AVOptionInfo {
+      class_name: class_name.clone().unwrap_or_default(),
+      name: string_from_ptr(option.name).unwrap_or_default(),
+      help: string_from_ptr(option.help),
+      option_type,
+      default: unsafe { OptionValue::default_of(option, option_type) },
+      min: option.min,
+      max: option.max,
+      flags: OptionFlags::from_bits_retain(option.flags),
+      unit,
+      constants,
+    });
+  }
+
+  let mut child = ptr::null_mut();
+  loop {
+    child = unsafe { ffi::av_opt_child_next(obj, child) };
+    if child.is_null() {
+      break;
+    }
+    unsafe { collect_options(child, options) };
+  }
+}
+
+/// Description of an option, see [`AVOptions::options`].
+#[derive(Debug, Clone)]
+pub struct AVOptionInfo {
+  /// Name of the class of the object holding the option, e.g.
+  /// `AVCodecContext`, or `libx264` for the private options of the encoder.
+  pub class_name: String,
+  pub name: String,
+  pub help: Option<String>,
+  pub option_type: OptionType,
+  pub default: OptionValue,
+  /// Minimum valid value for numeric options.
+  pub min: f64,
+  /// Maximum valid value for numeric options.
+  pub max: f64,
+  pub flags: OptionFlags,
+  /// Unit the named constants of the option belong to.
+  pub unit: Option<String>,
+  /// Named values the option accepts, e.g. the flags of a flags option.
+  pub constants: Vec<OptionConstant>,
+}
+
+/// Named value of an option, see [`AVOptionInfo::constants`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionConstant {
+  pub name: String,
+  pub help: Option<String>,
+  pub value: i64,
+}
+
+/// Type of an option, an `AV_OPT_TYPE_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionType {
+  Flags,
+  Int,
+  Int64,
+  Double,
+  Float,
+  String,
+  Rational,
+  Binary,
+  Dict,
+  UInt64,
+  ImageSize,
+  PixelFmt,
+  SampleFmt,
+  VideoRate,
+  Duration,
+  Color,
+  Bool,
+  ChLayout,
+  /// A type unknown to these bindings, e.g. the deprecated channel layout
+  /// mask.
+  Other(ffi::AVOptionType),
+}
+
+impl OptionType {
+  fn from_raw(option_type: ffi::AVOptionType) -> Self {
+    match option_type {
+      ffi::AV_OPT_TYPE_FLAGS => Self::Flags,
+      ffi::AV_OPT_TYPE_INT => Self::Int,
+      ffi::AV_OPT_TYPE_INT64 => Self::Int64,
+      ffi::AV_OPT_TYPE_DOUBLE => Self::Double,
+      ffi::AV_OPT_TYPE_FLOAT => Self::Float,
+      ffi::AV_OPT_TYPE_STRING => Self::String,
+      ffi::AV_OPT_TYPE_RATIONAL => Self::Rational,
+      ffi::AV_OPT_TYPE_BINARY => Self::Binary,
+      ffi::AV_OPT_TYPE_DICT => Self::Dict,
+      ffi::AV_OPT_TYPE_UINT64 => Self::UInt64,
+      ffi::AV_OPT_TYPE_IMAGE_SIZE => Self::ImageSize,
+      ffi::AV_OPT_TYPE_PIXEL_FMT => Self::PixelFmt,
+      ffi::AV_OPT_TYPE_SAMPLE_FMT => Self::SampleFmt,
+      ffi::AV_OPT_TYPE_VIDEO_RATE => Self::VideoRate,
+      ffi::AV_OPT_TYPE_DURATION => Self::Duration,
+      ffi::AV_OPT_TYPE_COLOR => Self::Color,
+      ffi::AV_OPT_TYPE_BOOL => Self::Bool,
+      ffi::AV_OPT_TYPE_CHLAYOUT => Self::ChLayout,
+      option_type => Self::Other(option_type),
+    }
+  }
+}
+
+/// Default value of an option, see [`AVOptionInfo::default`].
+#[derive(Debug, Clone)]
+pub enum OptionValue {
+  /// Integer, flags, bool, format and duration options.
+  Int(i64),
+  /// Floating point options.
+  Double(f64),
+  Rational(AVRational),
+  /// String-like options, e.g. strings, image sizes, video rates and
+  /// colors.
+  String(Option<String>),
+  /// Binary and dictionary options have no default.
+  None,
+}
+
+impl OptionValue {
+  /// # Safety
+  ///
+  /// `option_type` must be the type of `option`.
+  unsafe fn default_of(
+    option: &ffi::AVOption,
+    option_type: OptionType,
+  ) -> Self {
+    let value = option.default_val;
+    match option_type {
+      OptionType::Flags
+      | OptionType::Int
+      | OptionType::Int64
+      | OptionType::UInt64
+      | OptionType::PixelFmt
+      | OptionType::SampleFmt
+      | OptionType::Duration
+      | OptionType::Bool => Self::Int(unsafe { value.i64_ }),
+      OptionType::Double | OptionType::Float => {
+        Self::Double(unsafe { value.dbl })
+      }
+      OptionType::Rational => Self::Rational(unsafe { value.q }),
+      OptionType::String
+      | OptionType::ImageSize
+      | OptionType::VideoRate
+      | OptionType::Color
+      | OptionType::ChLayout => {
+        Self::String(string_from_ptr(unsafe { value.str_ }))
+      }
+      OptionType::Binary | OptionType::Dict | OptionType::Other(_) => {
+        Self::None
+      }
+    }
+  }
+}
+
+bitflags::bitflags! {
+  /// Flags of an option, a combination of `AV_OPT_FLAG_*`.
+  #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+  pub struct OptionFlags: i32 {
+    const ENCODING_PARAM = ffi::AV_OPT_FLAG_ENCODING_PARAM as i32;
+    const DECODING_PARAM = ffi::AV_OPT_FLAG_DECODING_PARAM as i32;
+    const AUDIO_PARAM = ffi::AV_OPT_FLAG_AUDIO_PARAM as i32;
+    const VIDEO_PARAM = ffi::AV_OPT_FLAG_VIDEO_PARAM as i32;
+    const SUBTITLE_PARAM = ffi::AV_OPT_FLAG_SUBTITLE_PARAM as i32;
+    /// The option is exported for the user to read, not to set.
+    const EXPORT = ffi::AV_OPT_FLAG_EXPORT as i32;
+    /// The option can't be set.
+    const READONLY = ffi::AV_OPT_FLAG_READONLY as i32;
+    const BSF_PARAM = ffi::AV_OPT_FLAG_BSF_PARAM as i32;
+    /// The option can be set after the object is initialized.
+    const RUNTIME_PARAM = ffi::AV_OPT_FLAG_RUNTIME_PARAM as i32;
+    const FILTERING_PARAM = ffi::AV_OPT_FLAG_FILTERING_PARAM as i32;
+    const DEPRECATED = ffi::AV_OPT_FLAG_DEPRECATED as i32;
+    const CHILD_CONSTS = ffi::AV_OPT_FLAG_CHILD_CONSTS as i32;
+
+    // Keep unknown flags of newer FFmpeg versions.
+    const _ = !0;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::{
+    avcodec::{AVCodec, AVCodecContext},
+    avfilter::{AVFilter, AVFilterGraph},
+    avutil::AVChannelLayout,
+    swresample::SwrContext,
+  };
+  use cstr::cstr;
+
+  #[test]
+  fn test_codec_context_options() {
+    let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+    let mut encode_context = AVCodecContext::new(&encoder);
+
+    let options = encode_context.options();
+    let bit_rate = options.iter().find(|x| x.name == "b").unwrap();
+    assert_eq!(bit_rate.class_name, "AVCodecContext");
+    assert_eq!(bit_rate.option_type, OptionType::Int64);
+    assert!(bit_rate.flags.contains(OptionFlags::ENCODING_PARAM));
+    let flags = options.iter().find(|x| x.name == "flags").unwrap();
+    assert!(flags.constants.iter().any(|x| x.name == "global_header"
+      && x.value == ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i64));
+    // Private option of the MPEG-4 encoder.
+    let data_partitioning = options
+      .iter()
+      .find(|x| x.name == "data_partitioning")
+      .unwrap();
+    assert_ne!(data_partitioning.class_name, "AVCodecContext");
+
+    encode_context.set_opt_int(cstr!("b"), 400_000).unwrap();
+    assert_eq!(encode_context.bit_rate, 400_000);
+    assert_eq!(encode_context.get_opt(cstr!("b")).unwrap(), cstr!("400000"));
+    encode_context
+      .set_opt(cstr!("data_partitioning"), cstr!("1"))
+      .unwrap();
+    assert_eq!(
+      encode_context
+        .get_opt_int(cstr!("data_partitioning"))
+        .unwrap(),
+      1
+    );
+    encode_context
+      .set_opt_q(cstr!("aspect"), AVRational { num: 4, den: 3 })
+      .unwrap();
+    let aspect = encode_context.get_opt_q(cstr!("aspect")).unwrap();
+    assert_eq!((aspect.num, aspect.den), (4, 3));
+
+    assert_eq!(
+      encode_context.set_opt_int(cstr!("no_such_option"), 1),
+      Err(RsmpegError::OptionNotFound("no_such_option".to_string()))
+    );
+    assert_eq!(
+      encode_context.get_opt(cstr!("no_such_option")),
+      Err(RsmpegError::OptionNotFound("no_such_option".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_initialized_options() {
+    let layout = AVChannelLayout::from_nb_channels(2);
+    let mut resample_context = SwrContext::new(
+      &layout,
+      ffi::AV_SAMPLE_FMT_S16,
+      44100,
+      &layout,
+      ffi::AV_SAMPLE_FMT_FLTP,
+      48000,
+    )
+    .unwrap();
+    resample_context.set_opt_int(cstr!("osr"), 22050).unwrap();
+    resample_context.init().unwrap();
+    assert_eq!(
+      resample_context.set_opt_int(cstr!("osr"), 44100),
+      Err(RsmpegError::OptionNotRuntime("osr".to_string()))
+    );
+    assert_eq!(resample_context.get_opt_int(cstr!("osr")).unwrap(), 22050);
+
+    // Only the volume itself can be changed at runtime.
+    let filter_graph = AVFilterGraph::new();
+    let mut volume = filter_graph
+      .create_filter_context(
+        &AVFilter::get_by_name(cstr!("volume")).unwrap(),
+        cstr!("volume"),
+        None,
+      )
+      .unwrap();
+    volume.set_opt(cstr!("volume"), cstr!("0.5")).unwrap();
+    assert_eq!(
+      volume.set_opt(cstr!("precision"), cstr!("fixed")),
+      Err(RsmpegError::OptionNotRuntime("precision".to_string()))
+    );
+    assert_eq!(
+      volume.set_opt(cstr!("no_such_option"), cstr!("1")),
+      Err(RsmpegError::OptionNotFound("no_such_option".to_string()))
+    );
+  }
+}
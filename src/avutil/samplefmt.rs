@@ -115,6 +115,39 @@ pub fn sample_fmt_is_planar(sample_fmt: AVSampleFormat) -> bool {
   unsafe { ffi::av_sample_fmt_is_planar(sample_fmt) == 1 }
 }
 
+/// Rust types of audio samples, with their packed and planar sample formats.
+///
+/// # Safety
+///
+/// The size and representation of `Self` must match the ones of a sample
+/// in both formats.
+pub unsafe trait SampleType: Copy + 'static {
+  /// Packed sample format of the type, e.g. `AV_SAMPLE_FMT_FLT` for `f32`.
+  const PACKED_FORMAT: AVSampleFormat;
+  /// Planar sample format of the type, e.g. `AV_SAMPLE_FMT_FLTP` for `f32`.
+  const PLANAR_FORMAT: AVSampleFormat;
+}
+
+macro_rules! sample_type {
+  ($($ty: ty => $packed: ident, $planar: ident;)*) => {
+    $(
+      unsafe impl SampleType for $ty {
+        const PACKED_FORMAT: AVSampleFormat = ffi::$packed;
+        const PLANAR_FORMAT: AVSampleFormat = ffi::$planar;
+      }
+    )*
+  };
+}
+
+sample_type! {
+  u8 => AV_SAMPLE_FMT_U8, AV_SAMPLE_FMT_U8P;
+  i16 => AV_SAMPLE_FMT_S16, AV_SAMPLE_FMT_S16P;
+  i32 => AV_SAMPLE_FMT_S32, AV_SAMPLE_FMT_S32P;
+  i64 => AV_SAMPLE_FMT_S64, AV_SAMPLE_FMT_S64P;
+  f32 => AV_SAMPLE_FMT_FLT, AV_SAMPLE_FMT_FLTP;
+  f64 => AV_SAMPLE_FMT_DBL, AV_SAMPLE_FMT_DBLP;
+}
+
 // The `nb_samples` of `AVSamples` is the capacity rather than length.
 // `nb_channels` and `audio_data.len()`(which is nb_planes) is only the same
 // when the audio sample format in planar.
@@ -130,12 +163,12 @@ pub fn sample_fmt_is_planar(sample_fmt: AVSampleFormat) -> bool {
 // audio data.
 wrap! {
     AVSamples: Box<[u8]>,
-    audio_data: Box<[*mut u8]> = Vec::new().into_boxed_slice(),
-    linesize: i32 = 0,
-    nb_channels: i32 = 0,
-    nb_samples: i32 = 0,
-    sample_fmt: AVSampleFormat = ffi::AV_SAMPLE_FMT_NONE,
-    align: i32 = 0,
+    pub audio_data: Box<[*mut u8]> = Vec::new().into_boxed_slice(),
+    pub linesize: i32 = 0,
+    pub nb_channels: i32 = 0,
+    pub nb_samples: i32 = 0,
+    pub sample_fmt: AVSampleFormat = ffi::AV_SAMPLE_FMT_NONE,
+    pub align: i32 = 0,
 }
 
 impl AVSamples {
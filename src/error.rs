@@ -2,11 +2,17 @@
 use libc::c_int;
 use std::{
   cmp::{Eq, PartialEq},
+  fmt::Write,
+  io,
   num::TryFromIntError,
 };
 use thiserror::Error;
 
-use crate::{ffi, shared::AVERROR_EAGAIN};
+use crate::{
+  avutil::err2str,
+  ffi,
+  shared::{AVERROR_EAGAIN, AVERROR_ENOMEM},
+};
 
 /// All the error variants of rs_ffmpeg.
 #[non_exhaustive]
@@ -16,6 +22,10 @@ pub enum RsmpegError {
   AVError(c_int),
   #[error("{0}")]
   CustomError(String),
+  /// An error with the FFmpeg log lines emitted during the failed call, see
+  /// [`LogCapture`](crate::avutil::LogCapture).
+  #[error("{}", display_logged(.0, .1))]
+  Logged(Box<RsmpegError>, Vec<String>),
 
   // --------- Unstablized error type below ------
 
@@ -30,6 +40,10 @@ pub enum RsmpegError {
   WriteHeaderError(c_int),
   #[error("Cannot write trailer to output file. ({0})")]
   WriteTrailerError(c_int),
+  #[error("Seek in the input file failed. ({0})")]
+  SeekError(c_int),
+  #[error("Blocking operation aborted by the interrupt callback.")]
+  Interrupted,
 
   #[error("Failed to open codec. ({0})")]
   CodecOpenError(c_int),
@@ -41,6 +55,10 @@ pub enum RsmpegError {
   CreateFilterError(c_int),
   #[error("Set property to a filter context failed. ({0})")]
   SetPropertyError(c_int),
+  #[error("Option not found: {0}")]
+  OptionNotFound(String),
+  #[error("Option can't be set after initialization: {0}")]
+  OptionNotRuntime(String),
 
   // Decoder errors
   #[error("Send packet to a codec context failed. ({0})")]
@@ -116,6 +134,9 @@ pub enum RsmpegError {
   #[error("Failed to fill data to image buffer. ({0})")]
   AVImageFillArrayError(c_int),
 
+  #[error("Streams are incompatible for stream copy: {0}")]
+  IncompatibleStreamsError(String),
+
   #[error("{0}")]
   TryFromIntError(TryFromIntError),
 
@@ -127,12 +148,15 @@ pub enum RsmpegError {
 impl RsmpegError {
   pub fn raw_error(&self) -> Option<c_int> {
     match self {
+      Self::Logged(error, _) => error.raw_error(),
+
       Self::AVError(err)
       | Self::OpenInputError(err)
       | Self::OpenOutputError(err)
       | Self::FindStreamInfoError(err)
       | Self::WriteHeaderError(err)
       | Self::WriteTrailerError(err)
+      | Self::SeekError(err)
       | Self::CodecOpenError(err)
       | Self::CodecSetParameterError(err)
       | Self::CreateFilterError(err)
@@ -163,6 +187,11 @@ impl RsmpegError {
       | Self::BitstreamDrainError
       | Self::EncoderDrainError => Some(AVERROR_EAGAIN),
 
+      Self::Interrupted => Some(ffi::AVERROR_EXIT),
+
+      Self::OptionNotFound(_) => Some(ffi::AVERROR_OPTION_NOT_FOUND),
+      Self::OptionNotRuntime(_) => Some(AVERROR_EINVAL),
+
       Self::BufferSinkEofError
       | Self::DecoderFlushedError
       | Self::EncoderFlushedError
@@ -171,12 +200,205 @@ impl RsmpegError {
       Self::AVFrameDoubleAllocatingError
       | Self::FilterNotFound
       | Self::CustomError(_)
+      | Self::IncompatibleStreamsError(_)
       | Self::TryFromIntError(_)
       | Self::Unknown => None,
     }
   }
+
+  /// Get the classification of the FFmpeg error code of the error, `None`
+  /// if it doesn't come from FFmpeg.
+  pub fn kind(&self) -> Option<AVErrorKind> {
+    self.raw_error().map(AVErrorKind::from_raw)
+  }
+
+  /// Get the error without the captured FFmpeg log lines.
+  pub fn inner(&self) -> &RsmpegError {
+    match self {
+      Self::Logged(error, _) => error.inner(),
+      error => error,
+    }
+  }
+
+  /// Get the FFmpeg log lines emitted during the failed call, empty if they
+  /// weren't captured with a [`LogCapture`](crate::avutil::LogCapture).
+  pub fn log_lines(&self) -> &[String] {
+    match self {
+      Self::Logged(_, lines) => lines,
+      _ => &[],
+    }
+  }
+}
+
+/// Display an error followed by the description of its code and the
+/// captured log lines.
+fn display_logged(error: &RsmpegError, lines: &[String]) -> String {
+  let mut message = error.to_string();
+  if let Some(description) = error.raw_error().and_then(err2str) {
+    let _ = write!(message, ": {description}");
+  }
+  for line in lines {
+    let _ = write!(message, "\n  {line}");
+  }
+  message
+}
+
+/// Classification of the FFmpeg error codes, i.e. the `AVERROR_*` constants
+/// and `AVERROR(errno)`.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AVErrorKind {
+  /// End of file, `AVERROR_EOF`.
+  Eof,
+  /// Resource temporarily unavailable, `AVERROR(EAGAIN)`.
+  Again,
+  /// Invalid argument, `AVERROR(EINVAL)`.
+  InvalidArgument,
+  /// Cannot allocate memory, `AVERROR(ENOMEM)`.
+  OutOfMemory,
+  /// Invalid data found when processing input, `AVERROR_INVALIDDATA`.
+  InvalidData,
+  /// Immediate exit was requested, `AVERROR_EXIT`.
+  Exit,
+  /// Not yet implemented in FFmpeg, patches welcome, `AVERROR_PATCHWELCOME`.
+  PatchWelcome,
+  /// Internal bug, `AVERROR_BUG` or `AVERROR_BUG2`.
+  Bug,
+  /// Buffer too small, `AVERROR_BUFFER_TOO_SMALL`.
+  BufferTooSmall,
+  /// Generic error in an external library, `AVERROR_EXTERNAL`.
+  External,
+  /// Unknown error, `AVERROR_UNKNOWN`.
+  Unknown,
+  /// Bitstream filter not found, `AVERROR_BSF_NOT_FOUND`.
+  BsfNotFound,
+  /// Decoder not found, `AVERROR_DECODER_NOT_FOUND`.
+  DecoderNotFound,
+  /// Demuxer not found, `AVERROR_DEMUXER_NOT_FOUND`.
+  DemuxerNotFound,
+  /// Encoder not found, `AVERROR_ENCODER_NOT_FOUND`.
+  EncoderNotFound,
+  /// Filter not found, `AVERROR_FILTER_NOT_FOUND`.
+  FilterNotFound,
+  /// Muxer not found, `AVERROR_MUXER_NOT_FOUND`.
+  MuxerNotFound,
+  /// Option not found, `AVERROR_OPTION_NOT_FOUND`.
+  OptionNotFound,
+  /// Protocol not found, `AVERROR_PROTOCOL_NOT_FOUND`.
+  ProtocolNotFound,
+  /// Stream not found, `AVERROR_STREAM_NOT_FOUND`.
+  StreamNotFound,
+  /// HTTP 400, `AVERROR_HTTP_BAD_REQUEST`.
+  HttpBadRequest,
+  /// HTTP 401, `AVERROR_HTTP_UNAUTHORIZED`.
+  HttpUnauthorized,
+  /// HTTP 403, `AVERROR_HTTP_FORBIDDEN`.
+  HttpForbidden,
+  /// HTTP 404, `AVERROR_HTTP_NOT_FOUND`.
+  HttpNotFound,
+  /// Other HTTP 4xx, `AVERROR_HTTP_OTHER_4XX`.
+  HttpOther4xx,
+  /// HTTP 5xx, `AVERROR_HTTP_SERVER_ERROR`.
+  HttpServerError,
+  /// Other POSIX error, `AVERROR(errno)` holding the errno.
+  Os(i32),
+  /// Unrecognized error code.
+  Other(c_int),
+}
+
+impl AVErrorKind {
+  /// Classify an FFmpeg error code.
+  pub fn from_raw(code: c_int) -> Self {
+    match code {
+      ffi::AVERROR_EOF => Self::Eof,
+      ffi::AVERROR_INVALIDDATA => Self::InvalidData,
+      ffi::AVERROR_EXIT => Self::Exit,
+      ffi::AVERROR_PATCHWELCOME => Self::PatchWelcome,
+      ffi::AVERROR_BUG | ffi::AVERROR_BUG2 => Self::Bug,
+      ffi::AVERROR_BUFFER_TOO_SMALL => Self::BufferTooSmall,
+      ffi::AVERROR_EXTERNAL => Self::External,
+      ffi::AVERROR_UNKNOWN => Self::Unknown,
+      ffi::AVERROR_BSF_NOT_FOUND => Self::BsfNotFound,
+      ffi::AVERROR_DECODER_NOT_FOUND => Self::DecoderNotFound,
+      ffi::AVERROR_DEMUXER_NOT_FOUND => Self::DemuxerNotFound,
+      ffi::AVERROR_ENCODER_NOT_FOUND => Self::EncoderNotFound,
+      ffi::AVERROR_FILTER_NOT_FOUND => Self::FilterNotFound,
+      ffi::AVERROR_MUXER_NOT_FOUND => Self::MuxerNotFound,
+      ffi::AVERROR_OPTION_NOT_FOUND => Self::OptionNotFound,
+      ffi::AVERROR_PROTOCOL_NOT_FOUND => Self::ProtocolNotFound,
+      ffi::AVERROR_STREAM_NOT_FOUND => Self::StreamNotFound,
+      ffi::AVERROR_HTTP_BAD_REQUEST => Self::HttpBadRequest,
+      ffi::AVERROR_HTTP_UNAUTHORIZED => Self::HttpUnauthorized,
+      ffi::AVERROR_HTTP_FORBIDDEN => Self::HttpForbidden,
+      ffi::AVERROR_HTTP_NOT_FOUND => Self::HttpNotFound,
+      ffi::AVERROR_HTTP_OTHER_4XX => Self::HttpOther4xx,
+      ffi::AVERROR_HTTP_SERVER_ERROR => Self::HttpServerError,
+      AVERROR_EAGAIN => Self::Again,
+      AVERROR_ENOMEM => Self::OutOfMemory,
+      AVERROR_EINVAL => Self::InvalidArgument,
+      // The tagged codes above are far below the errno range.
+      -4095..=-1 => Self::Os(-code),
+      code => Self::Other(code),
+    }
+  }
+
+  /// Get the closest [`io::ErrorKind`].
+  pub fn io_kind(self) -> io::ErrorKind {
+    match self {
+      Self::Eof => io::ErrorKind::UnexpectedEof,
+      Self::Again => io::ErrorKind::WouldBlock,
+      Self::InvalidArgument => io::ErrorKind::InvalidInput,
+      Self::OutOfMemory => io::ErrorKind::OutOfMemory,
+      Self::InvalidData => io::ErrorKind::InvalidData,
+      Self::PatchWelcome => io::ErrorKind::Unsupported,
+      Self::BsfNotFound
+      | Self::DecoderNotFound
+      | Self::DemuxerNotFound
+      | Self::EncoderNotFound
+      | Self::FilterNotFound
+      | Self::MuxerNotFound
+      | Self::OptionNotFound
+      | Self::ProtocolNotFound
+      | Self::StreamNotFound
+      | Self::HttpNotFound => io::ErrorKind::NotFound,
+      Self::HttpUnauthorized | Self::HttpForbidden => {
+        io::ErrorKind::PermissionDenied
+      }
+      // FFmpeg uses errno values on every platform, which aren't the raw OS
+      // errors of `io::Error` on Windows.
+      Self::Os(errno) => match errno as u32 {
+        ffi::ENOENT => io::ErrorKind::NotFound,
+        ffi::EACCES | ffi::EPERM => io::ErrorKind::PermissionDenied,
+        ffi::ECONNREFUSED => io::ErrorKind::ConnectionRefused,
+        ffi::ECONNRESET => io::ErrorKind::ConnectionReset,
+        ffi::ECONNABORTED => io::ErrorKind::ConnectionAborted,
+        ffi::ENOTCONN => io::ErrorKind::NotConnected,
+        ffi::EADDRINUSE => io::ErrorKind::AddrInUse,
+        ffi::EADDRNOTAVAIL => io::ErrorKind::AddrNotAvailable,
+        ffi::EPIPE => io::ErrorKind::BrokenPipe,
+        ffi::EEXIST => io::ErrorKind::AlreadyExists,
+        ffi::ETIMEDOUT => io::ErrorKind::TimedOut,
+        ffi::EINTR => io::ErrorKind::Interrupted,
+        ffi::ENOSYS => io::ErrorKind::Unsupported,
+        _ => io::ErrorKind::Other,
+      },
+      // `AVERROR_EXIT` isn't mapped to `Interrupted`, which means "retry" to
+      // IO code.
+      Self::Exit
+      | Self::Bug
+      | Self::BufferTooSmall
+      | Self::External
+      | Self::Unknown
+      | Self::HttpBadRequest
+      | Self::HttpOther4xx
+      | Self::HttpServerError
+      | Self::Other(_) => io::ErrorKind::Other,
+    }
+  }
 }
 
+const AVERROR_EINVAL: c_int = ffi::AVERROR(ffi::EINVAL);
+
 /// Overall result of Rsmpeg functions
 pub type Result<T, E = RsmpegError> = std::result::Result<T, E>;
 
@@ -194,3 +416,64 @@ impl From<TryFromIntError> for RsmpegError {
     Self::TryFromIntError(err)
   }
 }
+
+impl From<RsmpegError> for io::Error {
+  /// Keep the error as the inner error, with the kind of its code.
+  fn from(err: RsmpegError) -> Self {
+    let kind = err
+      .kind()
+      .map(AVErrorKind::io_kind)
+      .unwrap_or(io::ErrorKind::Other);
+    io::Error::new(kind, err)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_error_kind() {
+    assert_eq!(AVErrorKind::from_raw(ffi::AVERROR_EOF), AVErrorKind::Eof);
+    assert_eq!(AVErrorKind::from_raw(AVERROR_EAGAIN), AVErrorKind::Again);
+    assert_eq!(
+      AVErrorKind::from_raw(ffi::AVERROR_HTTP_NOT_FOUND),
+      AVErrorKind::HttpNotFound
+    );
+    assert_eq!(
+      AVErrorKind::from_raw(ffi::AVERROR(ffi::ENOENT)),
+      AVErrorKind::Os(ffi::ENOENT as i32)
+    );
+    assert_eq!(
+      AVErrorKind::from_raw(-0x7fff_0000),
+      AVErrorKind::Other(-0x7fff_0000)
+    );
+
+    assert_eq!(
+      RsmpegError::DecoderDrainError.kind(),
+      Some(AVErrorKind::Again)
+    );
+    assert_eq!(
+      RsmpegError::OpenInputError(ffi::AVERROR_INVALIDDATA).kind(),
+      Some(AVErrorKind::InvalidData)
+    );
+    assert_eq!(RsmpegError::FilterNotFound.kind(), None);
+  }
+
+  #[test]
+  fn test_io_error() {
+    let err =
+      io::Error::from(RsmpegError::OpenInputError(ffi::AVERROR(ffi::ENOENT)));
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    let inner = err.into_inner().unwrap().downcast::<RsmpegError>().unwrap();
+    assert!(matches!(*inner, RsmpegError::OpenInputError(_)));
+
+    let err = io::Error::from(RsmpegError::AVError(ffi::AVERROR(ffi::EPIPE)));
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+
+    let err = io::Error::from(RsmpegError::BufferSinkEofError);
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    let err = io::Error::from(RsmpegError::Interrupted);
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+  }
+}
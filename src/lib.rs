@@ -17,6 +17,7 @@ pub mod avformat;
 pub mod avutil;
 pub mod swresample;
 pub mod swscale;
+pub mod tools;
 
 pub mod error;
 
@@ -6,13 +6,13 @@ macro_rules! wrap_pure {
     (
         $(#[$meta:meta])*
         ($wrapped_type: ident): $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)*
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)*
     ) => {
         $(#[$meta])*
         pub struct $wrapped_type {
             something_should_not_be_touched_directly: std::ptr::NonNull<$ffi_type>,
-            // Publicize the attachment, can be directly changed without deref_mut()
-            $(pub $attach: $attach_type,)*
+            // A `pub` attachment can be directly changed without deref_mut()
+            $($attach_vis $attach: $attach_type,)*
         }
 
         impl $wrapped_type {
@@ -161,10 +161,10 @@ macro_rules! wrap_ref_mut {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
             wrap_ref_pure!(($name, [<$name Ref>]): $ffi_type);
             wrap_mut_pure!(($name, [<$name Mut>]): $ffi_type);
         }
@@ -176,10 +176,10 @@ macro_rules! wrap_ref {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
             wrap_ref_pure!(($name, [<$name Ref>]): $ffi_type);
         }
     };
@@ -190,10 +190,10 @@ macro_rules! wrap_mut {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
             wrap_mut_pure!(($name, [<$name Mut>]): $ffi_type);
         }
     };
@@ -204,10 +204,10 @@ macro_rules! wrap {
     (
         $(#[$meta:meta])*
         $name: ident: $ffi_type: ty
-        $(,$attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
+        $(,$attach_vis: vis $attach: ident: $attach_type: ty = $attach_default: expr)* $(,)?
     ) => {
         paste::paste! {
-            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach: $attach_type = $attach_default)*);
+            wrap_pure!($(#[$meta])* ($name): $ffi_type $(,$attach_vis $attach: $attach_type = $attach_default)*);
         }
     };
 }
@@ -2,7 +2,11 @@
 use crate::error::{Result, Ret, RsmpegError};
 use crate::ffi;
 use libc::c_int;
-use std::{ops::Deref, ptr::NonNull};
+use std::{
+  ffi::{c_char, CStr},
+  ops::Deref,
+  ptr::NonNull,
+};
 
 /// Triage a pointer to Some(non-null) or None
 pub trait PointerUpgrade<T>: Sized {
@@ -23,6 +27,14 @@ impl<T> PointerUpgrade<T> for *mut T {
   }
 }
 
+/// Copy a nullable C string.
+pub(crate) fn string_from_ptr(ptr: *const c_char) -> Option<String> {
+  ptr
+    .upgrade()
+    .map(|x| unsafe { CStr::from_ptr(x.as_ptr()) })
+    .map(|x| x.to_string_lossy().into_owned())
+}
+
 /// This is a common pattern in FFmpeg that an api returns Null as an error.
 /// We can set specific error code(Usually FFmpeg error code like
 /// ffi::AVERROR(ffi::ENOMEM)).
@@ -1,5 +1,11 @@
-use crate::{avutil::AVFrame, error::*, ffi, shared::*};
+use crate::{
+  avutil::{AVFrame, AVOptions},
+  error::*,
+  ffi,
+  shared::*,
+};
 use std::{
+  ffi::c_void,
   ops::Drop,
   ptr::{self, NonNull},
 };
@@ -186,6 +192,16 @@ impl SwrContext {
   }
 }
 
+unsafe impl AVOptions for SwrContext {
+  fn as_opt_ptr(&self) -> *mut c_void {
+    self.as_ptr() as *mut _
+  }
+
+  fn is_initialized(&self) -> bool {
+    SwrContext::is_initialized(self)
+  }
+}
+
 impl Drop for SwrContext {
   fn drop(&mut self) {
     let mut ptr = self.as_mut_ptr();
@@ -1,10 +1,10 @@
 use crate::{
-  avutil::{AVFrame, AVPixelFormat},
+  avutil::{AVFrame, AVOptions, AVPixelFormat},
   error::*,
   ffi,
   shared::*,
 };
-use std::ptr;
+use std::{ffi::c_void, ptr};
 wrap!(SwsContext: ffi::SwsContext);
 
 impl SwsContext {
@@ -157,6 +157,17 @@ impl SwsContext {
   }
 }
 
+unsafe impl AVOptions for SwsContext {
+  fn as_opt_ptr(&self) -> *mut c_void {
+    self.as_ptr() as *mut _
+  }
+
+  // Contexts are initialized on creation.
+  fn is_initialized(&self) -> bool {
+    true
+  }
+}
+
 impl Drop for SwsContext {
   fn drop(&mut self) {
     unsafe { ffi::sws_freeContext(self.as_mut_ptr()) }
@@ -0,0 +1,158 @@
+use crate::{
+  avformat::{AVFormatContextInput, AVFormatContextOutput},
+  avutil::av_rescale_q,
+  error::{Result, RsmpegError},
+  ffi,
+  tools::{JobControl, ProgressTracker},
+};
+use std::ffi::CStr;
+
+/// Concatenate `inputs` into `output` by stream copy, without decoding.
+///
+/// All inputs need the same stream layout as the first one, and the codec
+/// parameters of each stream need to be compatible with the ones of the
+/// same stream in the first input, see
+/// [`AVCodecParameters::incompatible_fields`](crate::avcodec::AVCodecParameters::incompatible_fields).
+/// Otherwise nothing is written and
+/// [`RsmpegError::IncompatibleStreamsError`] lists every incompatible field,
+/// re-encode the inputs in that case.
+///
+/// Each input starts where the previous one ends, i.e. the pts and dts of
+/// its packets are offset by the end of the latest packet of the previous
+/// inputs.
+pub fn concat_copy(inputs: &[&CStr], output: &CStr) -> Result<()> {
+  concat_copy_with(inputs, output, &mut JobControl::new())
+}
+
+/// [`concat_copy`] which reports its progress to and can be cancelled
+/// through `control`.
+///
+/// On cancellation the trailer is still written, so the output holds the
+/// packets copied so far, and [`RsmpegError::Interrupted`] is returned.
+pub fn concat_copy_with(
+  inputs: &[&CStr],
+  output: &CStr,
+  control: &mut JobControl,
+) -> Result<()> {
+  if inputs.is_empty() {
+    return Err(RsmpegError::CustomError(
+      "No input to concatenate.".to_string(),
+    ));
+  }
+  let mut input_format_contexts = inputs
+    .iter()
+    .map(|input| AVFormatContextInput::open(input, None, &mut None))
+    .collect::<Result<Vec<_>>>()?;
+  check_compatible(&input_format_contexts)?;
+
+  let mut output_format_context = AVFormatContextOutput::create(output, None)?;
+  for stream in input_format_contexts[0].streams() {
+    let mut codecpar = stream.codecpar().clone();
+    // The tag of the input container may be invalid in the output one.
+    codecpar.set_codec_tag(0);
+    let mut out_stream = output_format_context.new_stream();
+    out_stream.set_codecpar(codecpar);
+    out_stream.set_time_base(stream.time_base);
+  }
+  output_format_context.write_header(&mut None)?;
+
+  let duration = input_format_contexts
+    .iter()
+    .map(|x| x.duration)
+    .try_fold(0, |sum, x| (x > 0).then_some(sum + x))
+    .unwrap_or(0);
+  let mut tracker = ProgressTracker::new(0, duration);
+  let mut cancelled = false;
+
+  // End of the inputs copied so far, in `AV_TIME_BASE` units.
+  let mut end = 0;
+  'inputs: for input_format_context in &mut input_format_contexts {
+    let start = match input_format_context.start_time {
+      ffi::AV_NOPTS_VALUE => 0,
+      start_time => start_time,
+    };
+    let offset = end - start;
+
+    loop {
+      if control.is_cancelled() {
+        cancelled = true;
+        break 'inputs;
+      }
+      let Some(mut packet) = input_format_context.read_packet()? else {
+        break;
+      };
+      let stream_index = packet.stream_index as usize;
+      let time_base = input_format_context.streams()[stream_index].time_base;
+      let stream_offset = av_rescale_q(offset, ffi::AV_TIME_BASE_Q, time_base);
+      if packet.pts != ffi::AV_NOPTS_VALUE {
+        packet.set_pts(packet.pts + stream_offset);
+        end = end.max(av_rescale_q(
+          packet.pts + packet.duration,
+          time_base,
+          ffi::AV_TIME_BASE_Q,
+        ));
+      }
+      if packet.dts != ffi::AV_NOPTS_VALUE {
+        packet.set_dts(packet.dts + stream_offset);
+      }
+      packet.rescale_ts(
+        time_base,
+        output_format_context.streams()[stream_index].time_base,
+      );
+      packet.set_pos(-1);
+      tracker.packet_written(
+        &packet,
+        output_format_context.streams()[stream_index].time_base,
+      );
+      output_format_context.interleaved_write_frame(&mut packet)?;
+      tracker.report(control);
+    }
+  }
+
+  output_format_context.write_trailer()?;
+  tracker.report(control);
+  if cancelled {
+    Err(RsmpegError::Interrupted)
+  } else {
+    Ok(())
+  }
+}
+
+/// Check that every input can be stream copied into the streams of the
+/// first one.
+fn check_compatible(
+  input_format_contexts: &[AVFormatContextInput],
+) -> Result<()> {
+  let first = input_format_contexts[0].streams();
+  let mut problems = vec![];
+  for (input_index, input_format_context) in
+    input_format_contexts.iter().enumerate().skip(1)
+  {
+    let streams = input_format_context.streams();
+    if streams.len() != first.len() {
+      problems.push(format!(
+        "input {input_index} has {} streams instead of {}",
+        streams.len(),
+        first.len()
+      ));
+      continue;
+    }
+    for (stream_index, (stream, expected)) in
+      streams.iter().zip(first).enumerate()
+    {
+      let fields = stream.codecpar().incompatible_fields(&expected.codecpar());
+      if !fields.is_empty() {
+        problems.push(format!(
+          "input {input_index} stream {stream_index} differs in {}",
+          fields.join(", ")
+        ));
+      }
+    }
+  }
+
+  if problems.is_empty() {
+    Ok(())
+  } else {
+    Err(RsmpegError::IncompatibleStreamsError(problems.join("; ")))
+  }
+}
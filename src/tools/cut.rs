@@ -0,0 +1,574 @@
+use crate::{
+  avcodec::{
+    AVBSFContext, AVBSFContextUninit, AVBitStreamFilter, AVCodec,
+    AVCodecContext, AVCodecParameters, AVPacket,
+  },
+  avformat::{AVFormatContextInput, AVFormatContextOutput, SeekMode},
+  avutil::{av_inv_q, av_rescale_q, AVRational},
+  error::{Result, RsmpegError},
+  ffi,
+};
+use std::{ffi::CStr, mem, ops::Range, time::Duration};
+
+/// How [`cut`] handles cut points which don't fall on a keyframe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CutMode {
+  /// Widen the range to whole GOPs: the start snaps back to the keyframe at
+  /// or before it, and the end snaps forward to the keyframe at or after it.
+  /// Nothing is re-encoded, but the output may begin before `start` and end
+  /// after `end`.
+  KeyframeSnap,
+  /// Keep the exact range: the partial GOPs at both ends are decoded and
+  /// re-encoded with the codec and parameters of the input stream, the GOPs
+  /// in between are stream copied.
+  ///
+  /// The re-encoded frames carry their own in-band parameter sets, and the
+  /// copied H.264 and HEVC packets are converted to Annex B with in-band
+  /// parameter sets as well, so that decoders switch between them at each
+  /// keyframe. Open GOPs, whose leading pictures reference the previous
+  /// GOP, are not handled.
+  SmartReencodeEdges,
+}
+
+/// Cut the range `start..end` of `input` into `output` without re-encoding
+/// the whole file. Both bounds are relative to the start time of `input`.
+///
+/// Video, audio and subtitle streams are kept, and the timestamps of all of
+/// them are shifted so that the output starts at zero. How the cut points of
+/// the video stream are handled is decided by `mode`, other streams are cut
+/// at packet granularity to the same range as the video.
+pub fn cut(
+  input: &CStr,
+  output: &CStr,
+  start: Duration,
+  end: Duration,
+  mode: CutMode,
+) -> Result<()> {
+  if end <= start {
+    return Err(RsmpegError::CustomError(
+      "The end of the cut range is not after its start.".to_string(),
+    ));
+  }
+  let input_format_context =
+    AVFormatContextInput::open(input, None, &mut None)?;
+  let output_format_context = AVFormatContextOutput::create(output, None)?;
+  Cutter::new(
+    input_format_context,
+    output_format_context,
+    start,
+    end,
+    mode,
+  )?
+  .run()
+}
+
+/// Keeps the state of a [`cut`]. Timestamps without a stream are absolute
+/// ones in `AV_TIME_BASE` units.
+struct Cutter {
+  input_format_context: AVFormatContextInput,
+  output: Output,
+  mode: CutMode,
+  /// The requested range.
+  start: i64,
+  end: i64,
+  /// The range all streams are cut to, which is the requested one unless
+  /// it's snapped to keyframes. `None` until the snapped bound is known.
+  range_start: Option<i64>,
+  range_end: Option<i64>,
+  video: Option<VideoCut>,
+  /// Whether each input stream has reached the end of the range, dropped
+  /// streams are always finished.
+  finished: Vec<bool>,
+  /// Packets of other streams waiting for the snapped range to be known.
+  held: Vec<AVPacket>,
+}
+
+struct VideoCut {
+  stream_index: usize,
+  time_base: AVRational,
+  codecpar: AVCodecParameters,
+  /// Packets of the current GOP in decoding order, starting with a keyframe.
+  gop: Vec<AVPacket>,
+  /// Only needed by [`CutMode::SmartReencodeEdges`].
+  decode_context: Option<AVCodecContext>,
+  /// Converts copied packets to Annex B for [`CutMode::SmartReencodeEdges`].
+  bsf_context: Option<AVBSFContext>,
+}
+
+struct Output {
+  format_context: AVFormatContextOutput,
+  /// Output stream index and input time base of each input stream, `None`
+  /// for dropped streams.
+  streams: Vec<Option<(usize, AVRational)>>,
+}
+
+impl Cutter {
+  fn new(
+    mut input_format_context: AVFormatContextInput,
+    mut output_format_context: AVFormatContextOutput,
+    start: Duration,
+    end: Duration,
+    mode: CutMode,
+  ) -> Result<Self> {
+    let base = match input_format_context.start_time {
+      ffi::AV_NOPTS_VALUE => 0,
+      start_time => start_time,
+    };
+    let start = base + i64::try_from(start.as_micros())?;
+    let end = base.saturating_add(i64::try_from(end.as_micros())?);
+
+    let video_index = input_format_context
+      .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+      .map(|(index, _)| index);
+
+    let mut video = None;
+    let mut streams = vec![];
+    for (index, stream) in input_format_context.streams().iter().enumerate() {
+      let codecpar = stream.codecpar();
+      let codec_type = codecpar.codec_type();
+      if !(codec_type.is_video()
+        || codec_type.is_audio()
+        || codec_type.is_subtitle())
+        || (codec_type.is_video() && Some(index) != video_index)
+      {
+        streams.push(None);
+        continue;
+      }
+
+      let mut out_codecpar = codecpar.clone();
+      if Some(index) == video_index {
+        let mut video_cut = VideoCut {
+          stream_index: index,
+          time_base: stream.time_base,
+          codecpar: codecpar.clone(),
+          gop: vec![],
+          decode_context: None,
+          bsf_context: None,
+        };
+        if mode == CutMode::SmartReencodeEdges {
+          let decoder = AVCodec::find_decoder(codecpar.codec_id)
+            .ok_or(RsmpegError::AVError(ffi::AVERROR_DECODER_NOT_FOUND))?;
+          let mut decode_context = AVCodecContext::new(&decoder);
+          decode_context.apply_codecpar(&codecpar)?;
+          decode_context.set_pkt_timebase(stream.time_base);
+          if let Some(framerate) = stream.guess_framerate() {
+            decode_context.set_framerate(framerate);
+          }
+          decode_context.open(None)?;
+          video_cut.decode_context = Some(decode_context);
+
+          if let Some(bsf_context) =
+            annexb_bsf_context(&codecpar, stream.time_base)?
+          {
+            out_codecpar = bsf_context.par_out().clone();
+            video_cut.bsf_context = Some(bsf_context);
+          }
+        }
+        video = Some(video_cut);
+      }
+
+      // The tag of the input container may be invalid in the output one.
+      out_codecpar.set_codec_tag(0);
+      let mut out_stream = output_format_context.new_stream();
+      out_stream.set_codecpar(out_codecpar);
+      out_stream.set_time_base(stream.time_base);
+      streams.push(Some((out_stream.index as usize, stream.time_base)));
+    }
+
+    // Start reading from the keyframe at or before the start of the range.
+    match &video {
+      Some(video) => input_format_context.seek(
+        Some(video.stream_index),
+        av_rescale_q(start, ffi::AV_TIME_BASE_Q, video.time_base),
+        SeekMode::Backward,
+      )?,
+      None => input_format_context.seek(None, start, SeekMode::Backward)?,
+    }
+    output_format_context.write_header(&mut None)?;
+
+    // Snapped bounds are only known after reading the keyframes.
+    let (range_start, range_end) = match (&video, mode) {
+      (Some(_), CutMode::KeyframeSnap) => (None, None),
+      _ => (Some(start), Some(end)),
+    };
+
+    Ok(Self {
+      input_format_context,
+      finished: streams.iter().map(Option::is_none).collect(),
+      output: Output {
+        format_context: output_format_context,
+        streams,
+      },
+      mode,
+      start,
+      end,
+      range_start,
+      range_end,
+      video,
+      held: vec![],
+    })
+  }
+
+  fn run(mut self) -> Result<()> {
+    while !self.finished.iter().all(|&x| x) {
+      let Some(packet) = self.input_format_context.read_packet()? else {
+        break;
+      };
+      let stream_index = packet.stream_index as usize;
+      if self.finished.get(stream_index) != Some(&false) {
+        continue;
+      }
+      match &self.video {
+        Some(video) if video.stream_index == stream_index => {
+          self.push_video(packet)?
+        }
+        _ => self.push_other(packet)?,
+      }
+    }
+
+    // EOF, the last GOP has no next keyframe.
+    if let Some(video) = &self.video {
+      if !self.finished[video.stream_index] && !video.gop.is_empty() {
+        self.process_gop(None)?;
+      }
+    }
+    self.range_start.get_or_insert(self.start);
+    self.range_end.get_or_insert(i64::MAX);
+    self.release_held()?;
+
+    self.output.format_context.write_trailer()
+  }
+
+  fn push_video(&mut self, packet: AVPacket) -> Result<()> {
+    let video = self.video.as_ref().unwrap();
+    let timestamp = packet_timestamp(&packet);
+    if packet.flags & ffi::AV_PKT_FLAG_KEY as i32 == 0 {
+      // Packets before the first keyframe can't be decoded.
+      if !video.gop.is_empty() {
+        self.video.as_mut().unwrap().gop.push(packet);
+      }
+      return Ok(());
+    }
+    if timestamp == ffi::AV_NOPTS_VALUE {
+      return Err(RsmpegError::CustomError(
+        "Video keyframe without timestamp.".to_string(),
+      ));
+    }
+
+    let time = av_rescale_q(timestamp, video.time_base, ffi::AV_TIME_BASE_Q);
+    let stream_index = video.stream_index;
+    if !video.gop.is_empty() {
+      if time <= self.start {
+        // The GOP ends before the range, e.g. the seek landed on an earlier
+        // keyframe than the one at or before the start.
+        self.video.as_mut().unwrap().gop.clear();
+      } else {
+        self.process_gop(Some(timestamp))?;
+      }
+    }
+    if time >= self.end {
+      self.finished[stream_index] = true;
+      if self.range_end.is_none() {
+        self.range_end = Some(time);
+        self.release_held()?;
+      }
+      return Ok(());
+    }
+    self.video.as_mut().unwrap().gop.push(packet);
+    Ok(())
+  }
+
+  /// Handle a packet of a non-video stream.
+  fn push_other(&mut self, packet: AVPacket) -> Result<()> {
+    let stream_index = packet.stream_index as usize;
+    let (_, time_base) = self.output.streams[stream_index].unwrap();
+    let timestamp = packet_timestamp(&packet);
+    if timestamp == ffi::AV_NOPTS_VALUE {
+      return Ok(());
+    }
+    let time = av_rescale_q(timestamp, time_base, ffi::AV_TIME_BASE_Q);
+
+    let Some(range_start) = self.range_start else {
+      self.held.push(packet);
+      return Ok(());
+    };
+    if time < range_start {
+      return Ok(());
+    }
+    match self.range_end {
+      Some(range_end) if time >= range_end => {
+        self.finished[stream_index] = true;
+        Ok(())
+      }
+      // The snapped end is never before the requested one.
+      None if time >= self.end => {
+        self.held.push(packet);
+        Ok(())
+      }
+      _ => self.output.write(packet, range_start),
+    }
+  }
+
+  /// Handle the held packets again after a bound of the range is known.
+  fn release_held(&mut self) -> Result<()> {
+    for packet in mem::take(&mut self.held) {
+      self.push_other(packet)?;
+    }
+    Ok(())
+  }
+
+  /// Copy, re-encode or drop the buffered GOP, `next_keyframe` is the
+  /// timestamp of the keyframe starting the next GOP.
+  fn process_gop(&mut self, next_keyframe: Option<i64>) -> Result<()> {
+    if self.range_start.is_none() {
+      // The start snaps to the keyframe of the first kept GOP.
+      let video = self.video.as_ref().unwrap();
+      self.range_start = Some(av_rescale_q(
+        packet_timestamp(&video.gop[0]),
+        video.time_base,
+        ffi::AV_TIME_BASE_Q,
+      ));
+      self.release_held()?;
+    }
+    let video = self.video.as_mut().unwrap();
+    let gop = mem::take(&mut video.gop);
+    let range_start = self.range_start.unwrap();
+
+    if self.mode == CutMode::KeyframeSnap {
+      return copy_packets(video, &mut self.output, gop, range_start);
+    }
+
+    let start = av_rescale_q(self.start, ffi::AV_TIME_BASE_Q, video.time_base);
+    let end = av_rescale_q(self.end, ffi::AV_TIME_BASE_Q, video.time_base);
+    let gop_start = packet_timestamp(&gop[0]);
+    let gop_end = next_keyframe.unwrap_or_else(|| {
+      gop
+        .iter()
+        .map(|x| packet_timestamp(x) + x.duration)
+        .max()
+        .unwrap()
+    });
+    if gop_end <= start || gop_start >= end {
+      Ok(())
+    } else if gop_start >= start && gop_end <= end {
+      copy_packets(video, &mut self.output, gop, range_start)
+    } else {
+      reencode_packets(video, &mut self.output, gop, start..end, range_start)
+    }
+  }
+}
+
+impl Output {
+  /// Shift the timestamps of a packet of the input by `range_start`, then
+  /// write it to the corresponding output stream.
+  fn write(&mut self, mut packet: AVPacket, range_start: i64) -> Result<()> {
+    let (stream_index, time_base) =
+      self.streams[packet.stream_index as usize].unwrap();
+    let offset = av_rescale_q(range_start, ffi::AV_TIME_BASE_Q, time_base);
+    if packet.pts != ffi::AV_NOPTS_VALUE {
+      packet.set_pts(packet.pts - offset);
+    }
+    if packet.dts != ffi::AV_NOPTS_VALUE {
+      packet.set_dts(packet.dts - offset);
+    }
+    packet.rescale_ts(
+      time_base,
+      self.format_context.streams()[stream_index].time_base,
+    );
+    packet.set_stream_index(stream_index as i32);
+    packet.set_pos(-1);
+    self.format_context.interleaved_write_frame(&mut packet)
+  }
+}
+
+fn copy_packets(
+  video: &mut VideoCut,
+  output: &mut Output,
+  packets: Vec<AVPacket>,
+  range_start: i64,
+) -> Result<()> {
+  for mut packet in packets {
+    let Some(bsf_context) = &mut video.bsf_context else {
+      output.write(packet, range_start)?;
+      continue;
+    };
+    bsf_context.send_packet(Some(&mut packet))?;
+    loop {
+      match bsf_context.receive_packet(&mut packet) {
+        Ok(()) => output.write(packet.clone(), range_start)?,
+        Err(RsmpegError::BitstreamDrainError)
+        | Err(RsmpegError::BitstreamFlushedError) => break,
+        Err(e) => return Err(e),
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Decode `packets`, then encode the frames displayed within `range`, which
+/// is in the `time_base` of the video stream, with a new encoder.
+fn reencode_packets(
+  video: &mut VideoCut,
+  output: &mut Output,
+  packets: Vec<AVPacket>,
+  range: Range<i64>,
+  range_start: i64,
+) -> Result<()> {
+  // The encoder produces no B-frames, so giving the re-encoded packets the
+  // reorder delay of the GOP keeps dts monotonic around the copied ones.
+  let delay = match (packets[0].pts, packets[0].dts) {
+    (ffi::AV_NOPTS_VALUE, _) | (_, ffi::AV_NOPTS_VALUE) => 0,
+    (pts, dts) => (pts - dts).max(0),
+  };
+  let decode_context = video.decode_context.as_mut().unwrap();
+  let mut encoder: Option<Encoder> = None;
+
+  let mut packets = packets.into_iter();
+  let mut draining = false;
+  loop {
+    let mut frame = match decode_context.receive_frame() {
+      Ok(frame) => frame,
+      Err(RsmpegError::DecoderDrainError) => {
+        match packets.next() {
+          Some(packet) => decode_context.send_packet(Some(&packet))?,
+          None if !draining => {
+            decode_context.send_packet(None)?;
+            draining = true;
+          }
+          None => break,
+        }
+        continue;
+      }
+      Err(RsmpegError::DecoderFlushedError) => break,
+      Err(e) => return Err(e),
+    };
+
+    let pts = match frame.best_effort_timestamp {
+      ffi::AV_NOPTS_VALUE => frame.pts,
+      pts => pts,
+    };
+    if !range.contains(&pts) {
+      continue;
+    }
+    let encoder = match &mut encoder {
+      Some(x) => x,
+      None => encoder.insert(Encoder::open(
+        &video.codecpar,
+        video.time_base,
+        decode_context,
+        frame.format,
+      )?),
+    };
+    frame.set_pts(av_rescale_q(pts, video.time_base, encoder.time_base));
+    // Let the encoder decide the picture types.
+    frame.set_pict_type(ffi::AV_PICTURE_TYPE_NONE);
+    encoder.context.send_frame(Some(&frame))?;
+    encoder.write_packets(video.stream_index, output, delay, range_start)?;
+  }
+  // Be ready for the next GOP.
+  decode_context.flush_buffers();
+
+  if let Some(mut encoder) = encoder {
+    encoder.context.send_frame(None)?;
+    encoder.write_packets(video.stream_index, output, delay, range_start)?;
+  }
+  Ok(())
+}
+
+/// Encoder of the frames of a partial GOP.
+struct Encoder {
+  context: AVCodecContext,
+  time_base: AVRational,
+  /// `time_base` of the video stream.
+  stream_time_base: AVRational,
+}
+
+impl Encoder {
+  fn open(
+    codecpar: &AVCodecParameters,
+    stream_time_base: AVRational,
+    decode_context: &AVCodecContext,
+    pix_fmt: i32,
+  ) -> Result<Self> {
+    let encoder = AVCodec::find_encoder(codecpar.codec_id)
+      .ok_or(RsmpegError::AVError(ffi::AVERROR_ENCODER_NOT_FOUND))?;
+    let mut context = AVCodecContext::new(&encoder);
+    // Profile, level, colors, bit rate etc. follow the input.
+    context.apply_codecpar(codecpar)?;
+    context.set_pix_fmt(pix_fmt);
+    context.set_sample_aspect_ratio(decode_context.sample_aspect_ratio);
+    // Some encoders, e.g. MPEG-2, only accept the frame rate as time base.
+    let framerate = decode_context.framerate;
+    let time_base = if framerate.num > 0 && framerate.den > 0 {
+      context.set_framerate(framerate);
+      av_inv_q(framerate)
+    } else {
+      stream_time_base
+    };
+    context.set_time_base(time_base);
+    context.set_max_b_frames(0);
+    // No `AV_CODEC_FLAG_GLOBAL_HEADER`, parameter sets need to be in-band.
+    context.open(None)?;
+    Ok(Self {
+      context,
+      time_base,
+      stream_time_base,
+    })
+  }
+
+  /// Write the packets the encoder has produced to the video stream.
+  fn write_packets(
+    &mut self,
+    stream_index: usize,
+    output: &mut Output,
+    delay: i64,
+    range_start: i64,
+  ) -> Result<()> {
+    loop {
+      let mut packet = match self.context.receive_packet() {
+        Ok(packet) => packet,
+        Err(RsmpegError::EncoderDrainError)
+        | Err(RsmpegError::EncoderFlushedError) => return Ok(()),
+        Err(e) => return Err(e),
+      };
+      packet.rescale_ts(self.time_base, self.stream_time_base);
+      packet.set_dts(packet.pts - delay);
+      packet.set_stream_index(stream_index as i32);
+      output.write(packet, range_start)?;
+    }
+  }
+}
+
+/// Create the bitstream filter converting length prefixed H.264 or HEVC,
+/// e.g. of MP4 and Matroska, to Annex B. Return `None` for other codecs and
+/// for streams in Annex B already.
+fn annexb_bsf_context(
+  codecpar: &AVCodecParameters,
+  time_base: AVRational,
+) -> Result<Option<AVBSFContext>> {
+  let name: &[u8] = match codecpar.codec_id {
+    ffi::AV_CODEC_ID_H264 => b"h264_mp4toannexb\0",
+    ffi::AV_CODEC_ID_HEVC => b"hevc_mp4toannexb\0",
+    _ => return Ok(None),
+  };
+  // Length prefixed extradata (avcC and hvcC) starts with version 1.
+  if codecpar.extradata_size <= 0 || unsafe { *codecpar.extradata } != 1 {
+    return Ok(None);
+  }
+  let name = CStr::from_bytes_with_nul(name).unwrap();
+  let bsf = AVBitStreamFilter::find_by_name(name)
+    .ok_or(RsmpegError::AVError(ffi::AVERROR_BSF_NOT_FOUND))?;
+  let mut bsf_context = AVBSFContextUninit::new(&bsf);
+  bsf_context.set_par_in(codecpar);
+  bsf_context.set_time_base_in(time_base);
+  bsf_context.init().map(Some)
+}
+
+/// Get the pts of a packet if it's known, or the dts.
+fn packet_timestamp(packet: &AVPacket) -> i64 {
+  if packet.pts != ffi::AV_NOPTS_VALUE {
+    packet.pts
+  } else {
+    packet.dts
+  }
+}
@@ -0,0 +1,290 @@
+use crate::{
+  avcodec::{AVCodec, AVCodecContext},
+  avformat::{AVFormatContextInput, SeekMode},
+  avutil::{av_rescale_q, AVFrame, AVPixelFormat, AVRational},
+  error::{Result, RsmpegError},
+  ffi,
+  swscale::SwsContext,
+};
+use std::{ffi::CStr, time::Duration};
+
+/// Extract frames at exact timestamps from a video stream.
+///
+/// [`FrameGrabber`] seeks to the nearest keyframe before the requested
+/// timestamp, then decodes and discards frames until the frame displayed at
+/// that timestamp is reached. The decoder is kept open between requests, and
+/// requests moving forward by less than [`Self::seek_threshold`] continue
+/// decoding from the current position instead of seeking, which makes
+/// scrubbing cheap.
+pub struct FrameGrabber {
+  input_format_context: AVFormatContextInput,
+  decode_context: AVCodecContext,
+  stream_index: usize,
+  time_base: AVRational,
+  seek_threshold: Duration,
+  sws_context: Option<SwsContext>,
+  /// Last frame returned to the caller.
+  last: Option<AVFrame>,
+  /// Frame decoded ahead of the last returned one.
+  pending: Option<AVFrame>,
+}
+
+impl FrameGrabber {
+  /// Open `url` and create a [`FrameGrabber`] on its best video stream.
+  pub fn open(url: &CStr) -> Result<Self> {
+    let input_format_context =
+      AVFormatContextInput::open(url, None, &mut None)?;
+    Self::from_input(input_format_context, None)
+  }
+
+  /// Create a [`FrameGrabber`] on stream `stream_index` of the given input,
+  /// the best video stream is used if `stream_index` is `None`.
+  pub fn from_input(
+    input_format_context: AVFormatContextInput,
+    stream_index: Option<usize>,
+  ) -> Result<Self> {
+    let (stream_index, decoder) = match stream_index {
+      Some(stream_index) => {
+        let stream = input_format_context
+          .streams()
+          .get(stream_index)
+          .ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))?;
+        let decoder = AVCodec::find_decoder(stream.codecpar().codec_id)
+          .ok_or(RsmpegError::AVError(ffi::AVERROR_DECODER_NOT_FOUND))?;
+        (stream_index, decoder)
+      }
+      None => input_format_context
+        .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)?
+        .ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))?,
+    };
+
+    let (decode_context, time_base) = {
+      let stream = &input_format_context.streams()[stream_index];
+      let mut decode_context = AVCodecContext::new(&decoder);
+      decode_context.apply_codecpar(&stream.codecpar())?;
+      decode_context.set_pkt_timebase(stream.time_base);
+      if let Some(framerate) = stream.guess_framerate() {
+        decode_context.set_framerate(framerate);
+      }
+      decode_context.open(None)?;
+      (decode_context, stream.time_base)
+    };
+
+    Ok(Self {
+      input_format_context,
+      decode_context,
+      stream_index,
+      time_base,
+      seek_threshold: Duration::from_secs(2),
+      sws_context: None,
+      last: None,
+      pending: None,
+    })
+  }
+
+  /// Index of the stream frames are grabbed from.
+  pub fn stream_index(&self) -> usize {
+    self.stream_index
+  }
+
+  /// Get the inner [`AVFormatContextInput`].
+  pub fn input(&self) -> &AVFormatContextInput {
+    &self.input_format_context
+  }
+
+  /// Get the inner decoder [`AVCodecContext`].
+  pub fn decode_context(&self) -> &AVCodecContext {
+    &self.decode_context
+  }
+
+  /// Get how far a request may move forward before seeking instead of
+  /// decoding from the current position.
+  pub fn seek_threshold(&self) -> Duration {
+    self.seek_threshold
+  }
+
+  /// Set how far a request may move forward before seeking instead of
+  /// decoding from the current position, usually about the GOP length.
+  pub fn set_seek_threshold(&mut self, seek_threshold: Duration) {
+    self.seek_threshold = seek_threshold;
+  }
+
+  /// Get the frame displayed at `position`, which is relative to the start
+  /// time of the stream.
+  ///
+  /// Return `Ok(None)` if the stream contains no frame.
+  pub fn grab(&mut self, position: Duration) -> Result<Option<AVFrame>> {
+    let pts = self.position_to_pts(position)?;
+    self.grab_pts(pts)
+  }
+
+  /// Get the frame displayed at `pts`, which is in the `time_base` of the
+  /// stream. The last frame is returned if `pts` is beyond the end.
+  ///
+  /// Return `Ok(None)` if the stream contains no frame.
+  pub fn grab_pts(&mut self, pts: i64) -> Result<Option<AVFrame>> {
+    let threshold = self.duration_to_ts(self.seek_threshold)?;
+    let need_seek = match self.last.as_ref().map(frame_pts) {
+      Some(last_pts) if last_pts != ffi::AV_NOPTS_VALUE => {
+        pts < last_pts || pts - last_pts > threshold
+      }
+      _ => true,
+    };
+
+    let mut previous = if need_seek {
+      self.input_format_context.seek(
+        Some(self.stream_index),
+        pts,
+        SeekMode::Backward,
+      )?;
+      self.decode_context.flush_buffers();
+      self.pending = None;
+      self.last = None;
+      None
+    } else {
+      self.last.take()
+    };
+
+    while let Some(frame) = self.next_frame()? {
+      let frame_pts = frame_pts(&frame);
+      if frame_pts == ffi::AV_NOPTS_VALUE {
+        previous = Some(frame);
+        continue;
+      }
+      if frame_pts > pts {
+        // The previous frame is still on screen at `pts`, keep the frame just
+        // decoded for the next request.
+        return Ok(Some(match previous {
+          Some(previous) => {
+            self.pending = Some(frame);
+            self.finish(previous)
+          }
+          None => self.finish(frame),
+        }));
+      }
+      if frame_pts == pts
+        || (frame.duration > 0 && pts < frame_pts + frame.duration)
+      {
+        return Ok(Some(self.finish(frame)));
+      }
+      previous = Some(frame);
+    }
+
+    // Reached the end of the stream, the last frame is what displays.
+    Ok(previous.map(|frame| self.finish(frame)))
+  }
+
+  /// Similar to [`Self::grab`], but the returned frame is scaled to the given
+  /// size and pixel format with a cached [`SwsContext`].
+  pub fn grab_scaled(
+    &mut self,
+    position: Duration,
+    width: i32,
+    height: i32,
+    pix_fmt: AVPixelFormat,
+  ) -> Result<Option<AVFrame>> {
+    let Some(frame) = self.grab(position)? else {
+      return Ok(None);
+    };
+
+    let sws_context = match self.sws_context.take() {
+      Some(sws_context) => sws_context.get_cached_context(
+        frame.width,
+        frame.height,
+        frame.format,
+        width,
+        height,
+        pix_fmt,
+        ffi::SWS_BILINEAR,
+        None,
+        None,
+        None,
+      ),
+      None => SwsContext::get_context(
+        frame.width,
+        frame.height,
+        frame.format,
+        width,
+        height,
+        pix_fmt,
+        ffi::SWS_BILINEAR,
+        None,
+        None,
+        None,
+      ),
+    };
+    let sws_context =
+      self.sws_context.insert(sws_context.ok_or_else(|| {
+        RsmpegError::CustomError("Invalid swscontext parameter.".to_string())
+      })?);
+
+    let mut scaled_frame = AVFrame::new();
+    scaled_frame.set_width(width);
+    scaled_frame.set_height(height);
+    scaled_frame.set_format(pix_fmt);
+    scaled_frame.alloc_buffer()?;
+    sws_context.scale_frame(&frame, 0, frame.height, &mut scaled_frame)?;
+    scaled_frame.set_pts(frame.pts);
+    scaled_frame.set_pkt_dts(frame.pkt_dts);
+    scaled_frame.set_time_base(self.time_base);
+
+    Ok(Some(scaled_frame))
+  }
+
+  fn duration_to_ts(&self, duration: Duration) -> Result<i64> {
+    Ok(av_rescale_q(
+      i64::try_from(duration.as_micros())?,
+      ffi::AV_TIME_BASE_Q,
+      self.time_base,
+    ))
+  }
+
+  fn position_to_pts(&self, position: Duration) -> Result<i64> {
+    let stream = &self.input_format_context.streams()[self.stream_index];
+    let pts = self.duration_to_ts(position)?;
+    Ok(if stream.start_time == ffi::AV_NOPTS_VALUE {
+      pts
+    } else {
+      stream.start_time + pts
+    })
+  }
+
+  /// Remember the frame about to be returned.
+  fn finish(&mut self, frame: AVFrame) -> AVFrame {
+    self.last = Some(frame.clone());
+    frame
+  }
+
+  /// Pull the next decoded frame of the stream, return `Ok(None)` when the
+  /// decoder is fully drained.
+  fn next_frame(&mut self) -> Result<Option<AVFrame>> {
+    if let Some(frame) = self.pending.take() {
+      return Ok(Some(frame));
+    }
+    loop {
+      match self.decode_context.receive_frame() {
+        Ok(frame) => return Ok(Some(frame)),
+        Err(RsmpegError::DecoderDrainError) => {}
+        Err(RsmpegError::DecoderFlushedError) => return Ok(None),
+        Err(e) => return Err(e),
+      }
+
+      let packet = loop {
+        match self.input_format_context.read_packet()? {
+          Some(x) if x.stream_index != self.stream_index as i32 => {}
+          x => break x,
+        }
+      };
+      self.decode_context.send_packet(packet.as_ref())?;
+    }
+  }
+}
+
+/// Presentation timestamp of a decoded frame.
+fn frame_pts(frame: &AVFrame) -> i64 {
+  if frame.best_effort_timestamp != ffi::AV_NOPTS_VALUE {
+    frame.best_effort_timestamp
+  } else {
+    frame.pts
+  }
+}
@@ -0,0 +1,369 @@
+use crate::{
+  avformat::AVFormatContextInput,
+  avutil::AVRational,
+  error::{Result, RsmpegError},
+  ffi,
+};
+use std::{
+  fs::File,
+  io::{self, BufRead, BufReader, BufWriter, Read, Write},
+  path::Path,
+  str::FromStr,
+};
+
+const INDEX_HEADER: &str = "rs_ffmpeg keyframe index v1";
+
+/// A keyframe of a stream, timestamps are in the `time_base` of the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyframeEntry {
+  /// Presentation timestamp, `AV_NOPTS_VALUE` if unknown. It's always
+  /// unknown for entries read from the container index, which only have the
+  /// decoding timestamp.
+  pub pts: i64,
+  /// Decoding timestamp, `AV_NOPTS_VALUE` if unknown.
+  pub dts: i64,
+  /// Byte position of the packet in the file, -1 if unknown.
+  pub pos: i64,
+  /// Size of the packet in bytes.
+  pub size: i32,
+}
+
+impl KeyframeEntry {
+  /// Get the pts if it's known, or the dts.
+  pub fn timestamp(&self) -> i64 {
+    if self.pts != ffi::AV_NOPTS_VALUE {
+      self.pts
+    } else {
+      self.dts
+    }
+  }
+}
+
+/// Keyframes of one stream, sorted by [`KeyframeEntry::timestamp`], so
+/// lookups compare dts for an index read from the container and pts for a
+/// scanned one.
+#[derive(Debug, Clone)]
+pub struct StreamKeyframes {
+  pub stream_index: usize,
+  pub time_base: AVRational,
+  pub entries: Vec<KeyframeEntry>,
+}
+
+// `AVRational` doesn't implement `PartialEq`.
+impl PartialEq for StreamKeyframes {
+  fn eq(&self, other: &Self) -> bool {
+    self.stream_index == other.stream_index
+      && self.time_base.num == other.time_base.num
+      && self.time_base.den == other.time_base.den
+      && self.entries == other.entries
+  }
+}
+
+impl Eq for StreamKeyframes {}
+
+impl StreamKeyframes {
+  /// Get the last keyframe at or before `timestamp`, which is in the
+  /// `time_base` of the stream.
+  pub fn keyframe_before(&self, timestamp: i64) -> Option<&KeyframeEntry> {
+    let count = self
+      .entries
+      .partition_point(|entry| entry.timestamp() <= timestamp);
+    count.checked_sub(1).map(|index| &self.entries[index])
+  }
+
+  /// Get the first keyframe at or after `timestamp`, which is in the
+  /// `time_base` of the stream.
+  pub fn keyframe_after(&self, timestamp: i64) -> Option<&KeyframeEntry> {
+    let index = self
+      .entries
+      .partition_point(|entry| entry.timestamp() < timestamp);
+    self.entries.get(index)
+  }
+}
+
+/// Positions of the keyframes of every stream of a media file, for random
+/// access without decoding, e.g. trimming on keyframes or scrubbing.
+///
+/// The index can be written to disk with [`Self::write_to`] and loaded again
+/// with [`Self::read_from`], [`Self::is_valid_for`] tells whether a loaded
+/// index still matches the opened file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyframeIndex {
+  pub streams: Vec<StreamKeyframes>,
+  /// Size of the indexed file, -1 if unknown.
+  file_size: i64,
+  /// Duration of the indexed file in `AV_TIME_BASE` units.
+  duration: i64,
+}
+
+impl KeyframeIndex {
+  /// Build the keyframe index of `input_format_context`, from the index of
+  /// the container if every stream has one, or by scanning all the packets.
+  ///
+  /// Scanning reads the input to the end, seek back before reading packets
+  /// from it again.
+  pub fn build(
+    input_format_context: &mut AVFormatContextInput,
+  ) -> Result<Self> {
+    // Demuxers with a generic index only add the packets read so far to it,
+    // so it's incomplete.
+    let generic_index = input_format_context.iformat().flags
+      & ffi::AVFMT_GENERIC_INDEX as i32
+      != 0;
+    match (!generic_index)
+      .then(|| Self::from_container_index(input_format_context))
+      .flatten()
+    {
+      Some(index) => Ok(index),
+      None => Self::scan(input_format_context),
+    }
+  }
+
+  /// Build the keyframe index from the index entries the demuxer collected,
+  /// e.g. the sample table of MP4 or the cues of Matroska. Return `None` if
+  /// some stream has no index entry.
+  ///
+  /// Demuxers with `AVFMT_GENERIC_INDEX` only index the packets read so far,
+  /// use [`Self::build`] to fall back to scanning for them.
+  pub fn from_container_index(
+    input_format_context: &AVFormatContextInput,
+  ) -> Option<Self> {
+    let mut streams = vec![];
+    for (stream_index, stream) in
+      input_format_context.streams().iter().enumerate()
+    {
+      let stream_ptr = stream.as_ptr() as *mut ffi::AVStream;
+      let count = unsafe { ffi::avformat_index_get_entries_count(stream_ptr) };
+      if count <= 0 {
+        return None;
+      }
+      let entries = (0..count)
+        .filter_map(|i| unsafe {
+          ffi::avformat_index_get_entry(stream_ptr, i).as_ref()
+        })
+        .filter(|entry| entry.flags() & ffi::AVINDEX_KEYFRAME as i32 != 0)
+        .map(|entry| KeyframeEntry {
+          pts: ffi::AV_NOPTS_VALUE,
+          dts: entry.timestamp,
+          pos: entry.pos,
+          size: entry.size(),
+        })
+        .collect();
+      streams.push(StreamKeyframes {
+        stream_index,
+        time_base: stream.time_base,
+        entries,
+      });
+    }
+    Some(Self::new(input_format_context, streams))
+  }
+
+  /// Build the keyframe index by reading all the remaining packets of
+  /// `input_format_context`, seek back before reading packets from it again.
+  pub fn scan(input_format_context: &mut AVFormatContextInput) -> Result<Self> {
+    let mut streams: Vec<_> = input_format_context
+      .streams()
+      .iter()
+      .enumerate()
+      .map(|(stream_index, stream)| StreamKeyframes {
+        stream_index,
+        time_base: stream.time_base,
+        entries: vec![],
+      })
+      .collect();
+
+    for packet in input_format_context.packets() {
+      let packet = packet?;
+      if packet.flags & ffi::AV_PKT_FLAG_KEY as i32 == 0 {
+        continue;
+      }
+      if let Some(stream) = streams.get_mut(packet.stream_index as usize) {
+        stream.entries.push(KeyframeEntry {
+          pts: packet.pts,
+          dts: packet.dts,
+          pos: packet.pos,
+          size: packet.size,
+        });
+      }
+    }
+    for stream in &mut streams {
+      stream.entries.sort_by_key(|entry| entry.timestamp());
+    }
+
+    Ok(Self::new(input_format_context, streams))
+  }
+
+  /// Load the index cached at `cache_path` if it matches
+  /// `input_format_context`, or build it with [`Self::build`] and cache it
+  /// there.
+  pub fn load_or_build(
+    input_format_context: &mut AVFormatContextInput,
+    cache_path: impl AsRef<Path>,
+  ) -> Result<Self> {
+    let cache_path = cache_path.as_ref();
+    if let Ok(file) = File::open(cache_path) {
+      if let Ok(index) = Self::read_from(BufReader::new(file)) {
+        if index.is_valid_for(input_format_context) {
+          return Ok(index);
+        }
+      }
+    }
+
+    let index = Self::build(input_format_context)?;
+    File::create(cache_path)
+      .and_then(|file| index.write_to(BufWriter::new(file)))
+      .map_err(|e| {
+        RsmpegError::CustomError(format!("Cache keyframe index failed: {e}"))
+      })?;
+    Ok(index)
+  }
+
+  /// Get the keyframes of stream `stream_index`.
+  pub fn stream(&self, stream_index: usize) -> Option<&StreamKeyframes> {
+    self.streams.get(stream_index)
+  }
+
+  /// Whether this index, e.g. loaded from disk, describes the file opened by
+  /// `input_format_context`. Files are compared by size, duration and
+  /// stream layout.
+  pub fn is_valid_for(
+    &self,
+    input_format_context: &AVFormatContextInput,
+  ) -> bool {
+    let streams = input_format_context.streams();
+    self.file_size == file_size(input_format_context)
+      && self.duration == input_format_context.duration
+      && self.streams.len() == streams.len()
+      && self.streams.iter().zip(streams.iter()).all(|(x, stream)| {
+        x.time_base.num == stream.time_base.num
+          && x.time_base.den == stream.time_base.den
+      })
+  }
+
+  /// Serialize the index in a line based text format.
+  pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "{INDEX_HEADER}")?;
+    writeln!(
+      writer,
+      "{} {} {}",
+      self.file_size,
+      self.duration,
+      self.streams.len()
+    )?;
+    for stream in &self.streams {
+      writeln!(
+        writer,
+        "{} {} {} {}",
+        stream.stream_index,
+        stream.time_base.num,
+        stream.time_base.den,
+        stream.entries.len()
+      )?;
+      for entry in &stream.entries {
+        writeln!(
+          writer,
+          "{} {} {} {}",
+          entry.pts, entry.dts, entry.pos, entry.size
+        )?;
+      }
+    }
+    writer.flush()
+  }
+
+  /// Deserialize an index written by [`Self::write_to`].
+  pub fn read_from(reader: impl Read) -> io::Result<Self> {
+    let mut lines = BufReader::new(reader).lines();
+    let mut next_line = || {
+      lines.next().unwrap_or_else(|| {
+        Err(io::Error::new(
+          io::ErrorKind::UnexpectedEof,
+          "Truncated keyframe index.",
+        ))
+      })
+    };
+
+    if next_line()? != INDEX_HEADER {
+      return Err(invalid_data("Unknown keyframe index header."));
+    }
+    let [file_size, duration, nb_streams] = parse_fields(&next_line()?)?;
+    let nb_streams = to_int::<usize>(nb_streams)?;
+
+    // Counts aren't trusted for preallocation, the file may be corrupted.
+    let mut streams = vec![];
+    for _ in 0..nb_streams {
+      let [stream_index, num, den, nb_entries] = parse_fields(&next_line()?)?;
+      let time_base = AVRational {
+        num: to_int(num)?,
+        den: to_int(den)?,
+      };
+      if time_base.den <= 0 {
+        return Err(invalid_data("Invalid time base in keyframe index."));
+      }
+      let mut entries = vec![];
+      for _ in 0..to_int::<usize>(nb_entries)? {
+        let [pts, dts, pos, size] = parse_fields(&next_line()?)?;
+        entries.push(KeyframeEntry {
+          pts,
+          dts,
+          pos,
+          size: to_int(size)?,
+        });
+      }
+      streams.push(StreamKeyframes {
+        stream_index: to_int(stream_index)?,
+        time_base,
+        entries,
+      });
+    }
+
+    Ok(Self {
+      streams,
+      file_size,
+      duration,
+    })
+  }
+
+  fn new(
+    input_format_context: &AVFormatContextInput,
+    streams: Vec<StreamKeyframes>,
+  ) -> Self {
+    Self {
+      streams,
+      file_size: file_size(input_format_context),
+      duration: input_format_context.duration,
+    }
+  }
+}
+
+fn file_size(input_format_context: &AVFormatContextInput) -> i64 {
+  if input_format_context.pb.is_null() {
+    -1
+  } else {
+    unsafe { ffi::avio_size(input_format_context.pb) }
+  }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Convert a field to the type of the index, rejecting out of range values.
+fn to_int<T: TryFrom<i64>>(field: i64) -> io::Result<T> {
+  T::try_from(field)
+    .map_err(|_| invalid_data("Out of range value in keyframe index."))
+}
+
+fn parse_fields<const N: usize>(line: &str) -> io::Result<[i64; N]> {
+  let mut fields = [0; N];
+  let mut parts = line.split(' ');
+  for field in &mut fields {
+    *field = parts
+      .next()
+      .and_then(|x| i64::from_str(x).ok())
+      .ok_or_else(|| invalid_data("Malformed keyframe index line."))?;
+  }
+  if parts.next().is_some() {
+    return Err(invalid_data("Malformed keyframe index line."));
+  }
+  Ok(fields)
+}
@@ -0,0 +1,638 @@
+use crate::{
+  avcodec::{AVCodecDescriptor, AVCodecParametersRef},
+  avformat::{AVChapterRef, AVFormatContextInput, AVStreamRef, Disposition},
+  avutil::{
+    av_q2d, get_media_type_string, get_sample_fmt_name, AVDictionaryRef,
+    AVRational,
+  },
+  error::Result,
+  ffi,
+  shared::{string_from_ptr, PointerUpgrade},
+};
+use std::{collections::BTreeMap, ffi::CStr, time::Duration};
+
+/// Structured report of a media file, the information `ffprobe
+/// -show_format -show_streams -show_chapters` prints.
+///
+/// With the `serde` feature, it serializes to the same shape as the JSON
+/// output of `ffprobe -of json`: timestamps in seconds, rationals and bit
+/// rates as strings, and fields which aren't available are left out.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MediaInfo {
+  pub streams: Vec<StreamInfo>,
+  pub chapters: Vec<ChapterInfo>,
+  pub format: FormatInfo,
+}
+
+/// Information about the container.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FormatInfo {
+  pub filename: String,
+  pub nb_streams: usize,
+  pub nb_programs: usize,
+  /// Comma separated short names of the demuxer, e.g. `mov,mp4,m4a`.
+  pub format_name: String,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub format_long_name: Option<String>,
+  /// Start time in seconds.
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::seconds",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub start_time: Option<f64>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::duration",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub duration: Option<Duration>,
+  /// File size in bytes.
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::optional_string",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub size: Option<i64>,
+  /// Total bit rate in bits per second.
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::optional_string",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub bit_rate: Option<i64>,
+  pub probe_score: i32,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "BTreeMap::is_empty")
+  )]
+  pub tags: BTreeMap<String, String>,
+}
+
+/// Information about a stream and its codec parameters.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StreamInfo {
+  pub index: usize,
+  #[cfg_attr(feature = "serde", serde(skip))]
+  pub codec_id: ffi::AVCodecID,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub codec_name: Option<String>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub codec_long_name: Option<String>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub profile: Option<String>,
+  /// Media type, e.g. `video` or `audio`.
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub codec_type: Option<String>,
+  /// Fourcc of [`Self::codec_tag`], e.g. `avc1`.
+  pub codec_tag_string: String,
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::codec_tag"))]
+  pub codec_tag: u32,
+  /// Set for video streams.
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  pub video: Option<VideoInfo>,
+  /// Set for audio streams.
+  #[cfg_attr(feature = "serde", serde(flatten))]
+  pub audio: Option<AudioInfo>,
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::rational"))]
+  pub r_frame_rate: AVRational,
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::rational"))]
+  pub avg_frame_rate: AVRational,
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::rational"))]
+  pub time_base: AVRational,
+  /// Start timestamp in [`Self::time_base`].
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub start_pts: Option<i64>,
+  /// Start time in seconds.
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::seconds",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub start_time: Option<f64>,
+  /// Duration in [`Self::time_base`].
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub duration_ts: Option<i64>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::duration",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub duration: Option<Duration>,
+  /// Bit rate in bits per second.
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::optional_string",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub bit_rate: Option<i64>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::optional_string",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub nb_frames: Option<i64>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(serialize_with = "ffprobe::disposition")
+  )]
+  pub disposition: Disposition,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "BTreeMap::is_empty")
+  )]
+  pub tags: BTreeMap<String, String>,
+}
+
+/// Information specific to video streams.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VideoInfo {
+  pub width: i32,
+  pub height: i32,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::aspect_ratio",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub sample_aspect_ratio: Option<AVRational>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(
+      serialize_with = "ffprobe::aspect_ratio",
+      skip_serializing_if = "Option::is_none"
+    )
+  )]
+  pub display_aspect_ratio: Option<AVRational>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub pix_fmt: Option<String>,
+  pub level: i32,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub color_range: Option<String>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub color_space: Option<String>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub color_transfer: Option<String>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub color_primaries: Option<String>,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub chroma_location: Option<String>,
+  /// `progressive`, `tt`, `bb`, `tb` or `bt`.
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub field_order: Option<String>,
+}
+
+/// Information specific to audio streams.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AudioInfo {
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub sample_fmt: Option<String>,
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::string"))]
+  pub sample_rate: i32,
+  pub channels: i32,
+  /// Description of the channel layout, e.g. `stereo`, unset if the
+  /// channel order is unspecified.
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub channel_layout: Option<String>,
+  pub bits_per_sample: i32,
+}
+
+/// Information about a chapter.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ChapterInfo {
+  pub id: i64,
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::rational"))]
+  pub time_base: AVRational,
+  /// Start timestamp in [`Self::time_base`].
+  pub start: i64,
+  /// Start time in seconds.
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::time"))]
+  pub start_time: f64,
+  /// End timestamp in [`Self::time_base`].
+  pub end: i64,
+  /// End time in seconds.
+  #[cfg_attr(feature = "serde", serde(serialize_with = "ffprobe::time"))]
+  pub end_time: f64,
+  #[cfg_attr(
+    feature = "serde",
+    serde(skip_serializing_if = "BTreeMap::is_empty")
+  )]
+  pub tags: BTreeMap<String, String>,
+}
+
+impl MediaInfo {
+  /// Open the media file at `path` and build its report.
+  pub fn probe(path: &CStr) -> Result<Self> {
+    let input_format_context =
+      AVFormatContextInput::open(path, None, &mut None)?;
+    Ok(Self::from_input(&input_format_context))
+  }
+
+  /// Build the report of an opened input.
+  pub fn from_input(input_format_context: &AVFormatContextInput) -> Self {
+    let streams = input_format_context
+      .streams()
+      .iter()
+      .map(StreamInfo::new)
+      .collect();
+    let chapters = input_format_context
+      .chapters()
+      .iter()
+      .map(ChapterInfo::new)
+      .collect();
+    let format = FormatInfo::new(input_format_context);
+    Self {
+      streams,
+      chapters,
+      format,
+    }
+  }
+}
+
+impl FormatInfo {
+  fn new(input_format_context: &AVFormatContextInput) -> Self {
+    let iformat = input_format_context.iformat();
+    let size = input_format_context
+      .pb
+      .upgrade()
+      .map(|pb| unsafe { ffi::avio_size(pb.as_ptr()) })
+      .filter(|&x| x >= 0);
+    Self {
+      filename: string_from_ptr(input_format_context.url).unwrap_or_default(),
+      nb_streams: input_format_context.streams().len(),
+      nb_programs: input_format_context.programs().len(),
+      format_name: iformat.name().to_string_lossy().into_owned(),
+      format_long_name: iformat
+        .long_name()
+        .map(|x| x.to_string_lossy().into_owned()),
+      start_time: (input_format_context.start_time != ffi::AV_NOPTS_VALUE)
+        .then(|| {
+          input_format_context.start_time as f64 / ffi::AV_TIME_BASE as f64
+        }),
+      duration: (input_format_context.duration > 0)
+        .then(|| Duration::from_micros(input_format_context.duration as u64)),
+      size,
+      bit_rate: (input_format_context.bit_rate > 0)
+        .then_some(input_format_context.bit_rate),
+      probe_score: input_format_context.probe_score,
+      tags: tags(input_format_context.metadata()),
+    }
+  }
+}
+
+impl StreamInfo {
+  fn new(stream: &AVStreamRef) -> Self {
+    let codecpar = stream.codecpar();
+    let descriptor = AVCodecDescriptor::get(codecpar.codec_id);
+    let profile = (codecpar.profile != ffi::AV_PROFILE_UNKNOWN)
+      .then(|| {
+        string_from_ptr(unsafe {
+          ffi::avcodec_profile_name(codecpar.codec_id, codecpar.profile)
+        })
+      })
+      .flatten();
+    let codec_type = codecpar.codec_type();
+    let seconds = |ts: i64| ts as f64 * av_q2d(stream.time_base);
+
+    Self {
+      index: stream.index as usize,
+      codec_id: codecpar.codec_id,
+      codec_name: descriptor
+        .as_ref()
+        .map(|x| x.name().to_string_lossy().into_owned()),
+      codec_long_name: descriptor
+        .as_ref()
+        .and_then(|x| x.long_name())
+        .map(|x| x.to_string_lossy().into_owned()),
+      profile,
+      codec_type: get_media_type_string(codec_type.0)
+        .map(|x| x.to_string_lossy().into_owned()),
+      codec_tag_string: fourcc_string(codecpar.codec_tag),
+      codec_tag: codecpar.codec_tag,
+      video: codec_type
+        .is_video()
+        .then(|| VideoInfo::new(stream, &codecpar)),
+      audio: codec_type.is_audio().then(|| AudioInfo::new(&codecpar)),
+      r_frame_rate: stream.r_frame_rate,
+      avg_frame_rate: stream.avg_frame_rate,
+      time_base: stream.time_base,
+      start_pts: (stream.start_time != ffi::AV_NOPTS_VALUE)
+        .then_some(stream.start_time),
+      start_time: (stream.start_time != ffi::AV_NOPTS_VALUE)
+        .then(|| seconds(stream.start_time)),
+      duration_ts: (stream.duration != ffi::AV_NOPTS_VALUE)
+        .then_some(stream.duration),
+      duration: (stream.duration != ffi::AV_NOPTS_VALUE
+        && stream.duration >= 0)
+        .then(|| Duration::from_secs_f64(seconds(stream.duration))),
+      bit_rate: (codecpar.bit_rate > 0).then_some(codecpar.bit_rate),
+      nb_frames: (stream.nb_frames > 0).then_some(stream.nb_frames),
+      disposition: stream.disposition(),
+      tags: tags(stream.metadata()),
+    }
+  }
+}
+
+impl VideoInfo {
+  fn new(stream: &AVStreamRef, codecpar: &AVCodecParametersRef) -> Self {
+    let sample_aspect_ratio =
+      stream.guess_sample_aspect_ratio().filter(|x| x.num != 0);
+    let display_aspect_ratio = sample_aspect_ratio.map(|sar| {
+      let mut dar = AVRational { num: 0, den: 0 };
+      unsafe {
+        ffi::av_reduce(
+          &mut dar.num,
+          &mut dar.den,
+          codecpar.width as i64 * sar.num as i64,
+          codecpar.height as i64 * sar.den as i64,
+          1024 * 1024,
+        )
+      };
+      dar
+    });
+    let field_order = match codecpar.field_order {
+      ffi::AV_FIELD_PROGRESSIVE => Some("progressive"),
+      ffi::AV_FIELD_TT => Some("tt"),
+      ffi::AV_FIELD_BB => Some("bb"),
+      ffi::AV_FIELD_TB => Some("tb"),
+      ffi::AV_FIELD_BT => Some("bt"),
+      _ => None,
+    };
+
+    Self {
+      width: codecpar.width,
+      height: codecpar.height,
+      sample_aspect_ratio,
+      display_aspect_ratio,
+      pix_fmt: string_from_ptr(unsafe {
+        ffi::av_get_pix_fmt_name(codecpar.format)
+      }),
+      level: codecpar.level,
+      color_range: (codecpar.color_range != ffi::AVCOL_RANGE_UNSPECIFIED)
+        .then(|| {
+          string_from_ptr(unsafe {
+            ffi::av_color_range_name(codecpar.color_range)
+          })
+        })
+        .flatten(),
+      color_space: (codecpar.color_space != ffi::AVCOL_SPC_UNSPECIFIED)
+        .then(|| {
+          string_from_ptr(unsafe {
+            ffi::av_color_space_name(codecpar.color_space)
+          })
+        })
+        .flatten(),
+      color_transfer: (codecpar.color_trc != ffi::AVCOL_TRC_UNSPECIFIED)
+        .then(|| {
+          string_from_ptr(unsafe {
+            ffi::av_color_transfer_name(codecpar.color_trc)
+          })
+        })
+        .flatten(),
+      color_primaries: (codecpar.color_primaries != ffi::AVCOL_PRI_UNSPECIFIED)
+        .then(|| {
+          string_from_ptr(unsafe {
+            ffi::av_color_primaries_name(codecpar.color_primaries)
+          })
+        })
+        .flatten(),
+      chroma_location: (codecpar.chroma_location
+        != ffi::AVCHROMA_LOC_UNSPECIFIED)
+        .then(|| {
+          string_from_ptr(unsafe {
+            ffi::av_chroma_location_name(codecpar.chroma_location)
+          })
+        })
+        .flatten(),
+      field_order: field_order.map(String::from),
+    }
+  }
+}
+
+impl AudioInfo {
+  fn new(codecpar: &AVCodecParametersRef) -> Self {
+    let ch_layout = codecpar.ch_layout();
+    let channel_layout = (ch_layout.order != ffi::AV_CHANNEL_ORDER_UNSPEC)
+      .then(|| ch_layout.describe().ok())
+      .flatten()
+      .map(|x| x.to_string_lossy().into_owned());
+    Self {
+      sample_fmt: get_sample_fmt_name(codecpar.format)
+        .map(|x| x.to_string_lossy().into_owned()),
+      sample_rate: codecpar.sample_rate,
+      channels: ch_layout.nb_channels,
+      channel_layout,
+      bits_per_sample: unsafe {
+        ffi::av_get_bits_per_sample(codecpar.codec_id)
+      },
+    }
+  }
+}
+
+impl ChapterInfo {
+  fn new(chapter: &AVChapterRef) -> Self {
+    let seconds = |ts: i64| ts as f64 * av_q2d(chapter.time_base);
+    Self {
+      id: chapter.id,
+      time_base: chapter.time_base,
+      start: chapter.start,
+      start_time: seconds(chapter.start),
+      end: chapter.end,
+      end_time: seconds(chapter.end),
+      tags: tags(chapter.metadata()),
+    }
+  }
+}
+
+fn tags(metadata: Option<AVDictionaryRef>) -> BTreeMap<String, String> {
+  metadata
+    .map(|metadata| {
+      metadata
+        .iter()
+        .map(|entry| {
+          (
+            entry.key().to_string_lossy().into_owned(),
+            entry.value().to_string_lossy().into_owned(),
+          )
+        })
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Printable form of a fourcc, like `av_fourcc_make_string`.
+fn fourcc_string(tag: u32) -> String {
+  tag
+    .to_le_bytes()
+    .iter()
+    .map(|&c| {
+      if c.is_ascii_alphanumeric() || b" ._-".contains(&c) {
+        (c as char).to_string()
+      } else {
+        format!("[{c}]")
+      }
+    })
+    .collect()
+}
+
+/// Serializers formatting the values the way `ffprobe` prints them.
+#[cfg(feature = "serde")]
+mod ffprobe {
+  use super::*;
+  use bitflags::Flags;
+  use serde::{ser::SerializeMap, Serializer};
+  use std::fmt::Display;
+
+  pub fn string<S: Serializer, T: Display>(
+    value: &T,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+  }
+
+  pub fn optional_string<S: Serializer, T: Display>(
+    value: &Option<T>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    match value {
+      Some(value) => serializer.collect_str(value),
+      None => serializer.serialize_none(),
+    }
+  }
+
+  pub fn time<S: Serializer>(
+    seconds: &f64,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{seconds:.6}"))
+  }
+
+  pub fn seconds<S: Serializer>(
+    seconds: &Option<f64>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    match seconds {
+      Some(seconds) => time(seconds, serializer),
+      None => serializer.serialize_none(),
+    }
+  }
+
+  pub fn duration<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    seconds(&duration.map(|x| x.as_secs_f64()), serializer)
+  }
+
+  pub fn codec_tag<S: Serializer>(
+    tag: &u32,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("0x{tag:04x}"))
+  }
+
+  pub fn rational<S: Serializer>(
+    rational: &AVRational,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{}/{}", rational.num, rational.den))
+  }
+
+  pub fn aspect_ratio<S: Serializer>(
+    ratio: &Option<AVRational>,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    match ratio {
+      Some(ratio) => {
+        serializer.collect_str(&format_args!("{}:{}", ratio.num, ratio.den))
+      }
+      None => serializer.serialize_none(),
+    }
+  }
+
+  /// Every named disposition flag, set to `0` or `1`.
+  pub fn disposition<S: Serializer>(
+    disposition: &Disposition,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(None)?;
+    for flag in Disposition::FLAGS.iter().filter(|x| x.is_named()) {
+      map.serialize_entry(
+        &flag.name().to_lowercase(),
+        &(disposition.contains(*flag.value()) as i32),
+      )?;
+    }
+    map.end()
+  }
+}
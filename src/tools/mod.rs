@@ -0,0 +1,18 @@
+//! High-level helpers built on top of the FFmpeg wrappers.
+mod concat;
+mod cut;
+mod frame_grabber;
+mod keyframe_index;
+mod media_info;
+mod progress;
+mod stream_demuxer;
+mod transcoder;
+
+pub use concat::*;
+pub use cut::*;
+pub use frame_grabber::*;
+pub use keyframe_index::*;
+pub use media_info::*;
+pub use progress::*;
+pub use stream_demuxer::*;
+pub use transcoder::*;
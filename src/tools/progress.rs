@@ -0,0 +1,157 @@
+use crate::{
+  avcodec::AVPacket,
+  avformat::CancellationToken,
+  avutil::{av_rescale_q, AVRational},
+  ffi,
+};
+use std::time::{Duration, Instant};
+
+/// Progress of a long-running operation, e.g. [`Transcoder::run`] or
+/// [`concat_copy_with`].
+///
+/// [`Transcoder::run`]: crate::tools::Transcoder::run
+/// [`concat_copy_with`]: crate::tools::concat_copy_with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+  /// Output timestamp reached, i.e. the end of the latest packet written,
+  /// relative to the start of the output.
+  pub position: Duration,
+  /// Total duration of the output estimated from the duration of the
+  /// inputs, if it's known.
+  pub duration: Option<Duration>,
+  /// Number of frames encoded, zero if nothing is re-encoded.
+  pub frames: u64,
+  /// Number of packets written.
+  pub packets: u64,
+  /// Output duration processed per second of wall clock time, e.g. `2.0`
+  /// means twice as fast as real time.
+  pub speed: Option<f64>,
+  /// Bitrate estimated from the bytes of the packets written, in bits per
+  /// second.
+  pub bitrate: Option<f64>,
+}
+
+impl Progress {
+  /// Get the fraction done in `0.0..=1.0`, if the duration is known.
+  pub fn fraction(&self) -> Option<f64> {
+    let duration = self.duration.filter(|x| !x.is_zero())?;
+    Some((self.position.as_secs_f64() / duration.as_secs_f64()).min(1.0))
+  }
+}
+
+pub type ProgressCallback = Box<dyn FnMut(&Progress) + Send + 'static>;
+
+/// Progress reporting and cooperative cancellation of a long-running
+/// operation.
+///
+/// Cancellation is checked between packets. Once the token is cancelled,
+/// the operation stops reading, flushes what it has buffered, writes the
+/// trailer so the output stays valid, and returns
+/// [`RsmpegError::Interrupted`](crate::error::RsmpegError::Interrupted).
+#[derive(Default)]
+pub struct JobControl {
+  progress_callback: Option<ProgressCallback>,
+  cancellation_token: Option<CancellationToken>,
+}
+
+impl JobControl {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Set the callback called with the progress after each packet the
+  /// operation processes, and once more after the trailer is written.
+  pub fn set_progress_callback(
+    &mut self,
+    callback: impl FnMut(&Progress) + Send + 'static,
+  ) {
+    self.progress_callback = Some(Box::new(callback));
+  }
+
+  /// Set the token which cancels the operation, cancel it from any thread.
+  pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+    self.cancellation_token = Some(token);
+  }
+
+  /// Whether the operation is asked to stop.
+  pub fn is_cancelled(&self) -> bool {
+    self
+      .cancellation_token
+      .as_ref()
+      .is_some_and(CancellationToken::is_cancelled)
+  }
+}
+
+/// Accumulates the [`Progress`] of an operation.
+pub(crate) struct ProgressTracker {
+  started: Instant,
+  /// Output timestamp of the start of the output, in `AV_TIME_BASE` units.
+  start_time: i64,
+  /// End of the latest packet written, in `AV_TIME_BASE` units.
+  end_time: Option<i64>,
+  duration: Option<Duration>,
+  frames: u64,
+  packets: u64,
+  bytes: u64,
+}
+
+impl ProgressTracker {
+  /// `start_time` is the output timestamp the output starts at, in
+  /// `AV_TIME_BASE` units, `duration` is the expected output duration in
+  /// the same unit, non-positive if unknown.
+  pub fn new(start_time: i64, duration: i64) -> Self {
+    Self {
+      started: Instant::now(),
+      start_time,
+      end_time: None,
+      duration: (duration > 0).then(|| Duration::from_micros(duration as u64)),
+      frames: 0,
+      packets: 0,
+      bytes: 0,
+    }
+  }
+
+  /// Count a frame sent to an encoder.
+  pub fn frame_encoded(&mut self) {
+    self.frames += 1;
+  }
+
+  /// Count a packet about to be written, its timestamps are in `time_base`.
+  pub fn packet_written(&mut self, packet: &AVPacket, time_base: AVRational) {
+    self.packets += 1;
+    self.bytes += packet.size.max(0) as u64;
+    let timestamp = match packet.pts {
+      ffi::AV_NOPTS_VALUE => packet.dts,
+      pts => pts,
+    };
+    if timestamp != ffi::AV_NOPTS_VALUE {
+      let end = av_rescale_q(
+        timestamp + packet.duration.max(0),
+        time_base,
+        ffi::AV_TIME_BASE_Q,
+      );
+      self.end_time = Some(self.end_time.map_or(end, |x| x.max(end)));
+    }
+  }
+
+  /// Call the progress callback of `control` with the current progress.
+  pub fn report(&self, control: &mut JobControl) {
+    let Some(callback) = &mut control.progress_callback else {
+      return;
+    };
+    let position = self
+      .end_time
+      .map(|x| Duration::from_micros((x - self.start_time).max(0) as u64))
+      .unwrap_or_default();
+    let seconds = position.as_secs_f64();
+    let elapsed = self.started.elapsed().as_secs_f64();
+    callback(&Progress {
+      position,
+      duration: self.duration,
+      frames: self.frames,
+      packets: self.packets,
+      speed: (elapsed > 0.0).then(|| seconds / elapsed),
+      bitrate: (seconds > 0.0).then(|| self.bytes as f64 * 8.0 / seconds),
+    });
+  }
+}
@@ -0,0 +1,188 @@
+use crate::{avcodec::AVPacket, avformat::AVFormatContextInput, error::Result};
+use std::{
+  collections::VecDeque,
+  sync::{Arc, Condvar, Mutex, MutexGuard},
+};
+
+/// Route the packets of an [`AVFormatContextInput`] into bounded per-stream
+/// queues, so that each stream can be consumed independently, e.g. video and
+/// audio on different threads.
+///
+/// Packets are read lazily by whichever [`StreamReceiver`] runs out of queued
+/// packets. When the queue a packet is routed to is full, the reading
+/// receiver blocks until the consumer of that stream catches up, which
+/// bounds the memory used. Packets of streams without a receiver are
+/// dropped.
+///
+/// Since a full queue blocks the reader, the receivers of one demuxer should
+/// not be consumed alternately on a single thread unless the queues are big
+/// enough to hold the interleaving distance.
+pub struct StreamDemuxer {
+  shared: Arc<Shared>,
+}
+
+/// Receiving side of one stream of a [`StreamDemuxer`], dropping it drops
+/// the queued and future packets of the stream.
+pub struct StreamReceiver {
+  shared: Arc<Shared>,
+  stream_index: usize,
+}
+
+struct Shared {
+  state: Mutex<State>,
+  condvar: Condvar,
+  capacity: usize,
+}
+
+struct State {
+  /// `None` while a receiver is reading from it and queueing the packet.
+  input_format_context: Option<AVFormatContextInput>,
+  /// `None` for streams without a receiver.
+  queues: Vec<Option<VecDeque<AVPacket>>>,
+  /// EOF or an error is reached.
+  finished: bool,
+}
+
+impl StreamDemuxer {
+  /// Create a [`StreamDemuxer`] on `input_format_context`, each stream queues
+  /// at most `capacity` packets. A `capacity` of zero is treated as one.
+  pub fn new(
+    input_format_context: AVFormatContextInput,
+    capacity: usize,
+  ) -> Self {
+    let nb_streams = input_format_context.streams().len();
+    Self {
+      shared: Arc::new(Shared {
+        state: Mutex::new(State {
+          input_format_context: Some(input_format_context),
+          queues: (0..nb_streams).map(|_| None).collect(),
+          finished: false,
+        }),
+        condvar: Condvar::new(),
+        capacity: capacity.max(1),
+      }),
+    }
+  }
+
+  /// Create the receiver of stream `stream_index`. Return `None` if the
+  /// index is invalid or the receiver has already been created.
+  ///
+  /// Receivers should be created before consuming any of them, packets read
+  /// before a stream has a receiver are dropped.
+  pub fn receiver(&self, stream_index: usize) -> Option<StreamReceiver> {
+    let mut state = self.shared.lock();
+    let queue = state.queues.get_mut(stream_index)?;
+    if queue.is_some() {
+      return None;
+    }
+    *queue = Some(VecDeque::with_capacity(self.shared.capacity));
+    Some(StreamReceiver {
+      shared: self.shared.clone(),
+      stream_index,
+    })
+  }
+}
+
+impl StreamReceiver {
+  /// Index of the stream this receiver pulls packets of.
+  pub fn stream_index(&self) -> usize {
+    self.stream_index
+  }
+
+  /// Get the next packet of the stream, reading from the input when the
+  /// queue is empty.
+  ///
+  /// Return `Ok(None)` on EOF. A read error is returned to the receiver
+  /// that hit it, the other receivers see EOF after draining their queues.
+  pub fn recv(&self) -> Result<Option<AVPacket>> {
+    let shared = &*self.shared;
+    loop {
+      let mut state = shared.lock();
+      let mut input_format_context = loop {
+        if let Some(packet) = state.queue(self.stream_index).pop_front() {
+          // The reader may be waiting for space in this queue.
+          shared.condvar.notify_all();
+          return Ok(Some(packet));
+        }
+        if state.finished {
+          return Ok(None);
+        }
+        match state.input_format_context.take() {
+          Some(x) => break x,
+          // Another receiver is reading.
+          None => state = shared.wait(state),
+        }
+      };
+      drop(state);
+
+      let result = input_format_context.read_packet();
+
+      let mut state = shared.lock();
+      let result = match result {
+        Ok(Some(packet)) => {
+          let index = packet.stream_index as usize;
+          // Backpressure: wait for the consumer of the stream to catch up.
+          while matches!(
+            state.queues.get(index),
+            Some(Some(queue)) if queue.len() >= shared.capacity
+          ) {
+            state = shared.wait(state);
+          }
+          if let Some(Some(queue)) = state.queues.get_mut(index) {
+            queue.push_back(packet);
+          }
+          Ok(())
+        }
+        Ok(None) => {
+          state.finished = true;
+          Ok(())
+        }
+        Err(e) => {
+          state.finished = true;
+          Err(e)
+        }
+      };
+      // Give back the input after the packet is queued, so packets of a
+      // stream are never reordered by concurrent readers.
+      state.input_format_context = Some(input_format_context);
+      shared.condvar.notify_all();
+      result?;
+    }
+  }
+}
+
+impl Iterator for StreamReceiver {
+  type Item = Result<AVPacket>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    self.recv().transpose()
+  }
+}
+
+impl Drop for StreamReceiver {
+  fn drop(&mut self) {
+    let mut state = self.shared.lock();
+    state.queues[self.stream_index] = None;
+    // Wake up the reader if it's waiting for space in this queue.
+    self.shared.condvar.notify_all();
+  }
+}
+
+impl Shared {
+  fn lock(&self) -> MutexGuard<'_, State> {
+    // The state is always consistent between operations, so recover from
+    // poisoning caused by a panicking receiver.
+    self.state.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn wait<'a>(&self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+    self.condvar.wait(state).unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+impl State {
+  fn queue(&mut self, stream_index: usize) -> &mut VecDeque<AVPacket> {
+    // A receiver keeps its queue alive until it's dropped.
+    self.queues[stream_index].as_mut().unwrap()
+  }
+}
@@ -0,0 +1,618 @@
+use crate::{
+  avcodec::{AVCodec, AVCodecContext, AVCodecRef, AVPacket},
+  avfilter::{AVFilter, AVFilterGraph, AVFilterInOut},
+  avformat::{AVFormatContextInput, AVFormatContextOutput, AVStreamRef},
+  avutil::{
+    av_inv_q, av_rescale_q, ra, AVChannelLayout, AVDictionary, AVFrame,
+  },
+  error::{Result, RsmpegError},
+  ffi,
+  tools::{JobControl, Progress, ProgressTracker},
+};
+use std::ffi::{CStr, CString};
+
+const BUFFERSRC_NAME: &[u8] = b"in\0";
+const BUFFERSINK_NAME: &[u8] = b"out\0";
+
+/// Get a nul terminated name as a `CStr`.
+fn name(bytes: &'static [u8]) -> &'static CStr {
+  CStr::from_bytes_with_nul(bytes).unwrap()
+}
+
+/// How [`Transcoder`] handles a stream of the input.
+#[derive(Debug, Clone)]
+pub enum StreamPolicy {
+  /// Copy the packets without decoding.
+  Copy,
+  /// Decode, filter and encode the stream, only for video and audio.
+  Reencode(EncoderConfig),
+  /// Leave the stream out of the output.
+  Drop,
+}
+
+/// Encoder settings of [`StreamPolicy::Reencode`]. Fields left `None` follow
+/// the input stream, the filter output or the encoder defaults.
+///
+/// Dimensions, frame rate, sample rate and channel layout of the output are
+/// the ones the filter produces, change them with the filter description.
+#[derive(Debug, Clone, Default)]
+pub struct EncoderConfig {
+  /// Name of the encoder, e.g. `libx264`. The default encoder of
+  /// `codec_id` is used if it's `None`.
+  pub encoder: Option<CString>,
+  /// Codec to encode to, defaults to the codec of the input stream.
+  pub codec_id: Option<ffi::AVCodecID>,
+  /// Pixel format for video or sample format for audio. Defaults to the
+  /// formats the encoder supports, the filter converts to one of them.
+  pub format: Option<i32>,
+  pub bit_rate: Option<i64>,
+  pub gop_size: Option<i32>,
+  pub max_b_frames: Option<i32>,
+  /// Options of the encoder, e.g. `preset` of libx264.
+  pub options: Vec<(CString, CString)>,
+}
+
+/// Transcode an input into an output, each stream is copied, re-encoded or
+/// dropped according to its [`StreamPolicy`].
+///
+/// Re-encoded streams go through decoder, filter graph, encoder and muxer,
+/// with timestamps rescaled along the way and every stage flushed at the
+/// end. Every video, audio and subtitle stream is copied by default.
+///
+/// Progress is reported and cancellation is checked through the
+/// [`JobControl`] of the transcoder, see [`Self::control_mut`].
+pub struct Transcoder {
+  input_format_context: AVFormatContextInput,
+  output_format_context: AVFormatContextOutput,
+  policies: Vec<StreamPolicy>,
+  /// Filter description of each stream, only used by re-encoded ones.
+  filters: Vec<Option<CString>>,
+  control: JobControl,
+}
+
+/// A stream of the input while running.
+enum StreamContext {
+  Dropped,
+  Copied { stream_index: usize },
+  Reencoded(Box<ReencodeContext>),
+}
+
+/// The muxer and the progress of what's written to it.
+struct Output {
+  format_context: AVFormatContextOutput,
+  tracker: ProgressTracker,
+}
+
+struct ReencodeContext {
+  decode_context: AVCodecContext,
+  filter_graph: AVFilterGraph,
+  encode_context: AVCodecContext,
+  stream_index: usize,
+}
+
+impl Transcoder {
+  /// Open `input` and create `output`, the muxer is guessed from the
+  /// extension of `output`.
+  pub fn new(input: &CStr, output: &CStr) -> Result<Self> {
+    let input_format_context =
+      AVFormatContextInput::open(input, None, &mut None)?;
+    let output_format_context = AVFormatContextOutput::create(output, None)?;
+    Ok(Self::from_contexts(
+      input_format_context,
+      output_format_context,
+    ))
+  }
+
+  /// Create a [`Transcoder`] on an opened input and a created output, e.g.
+  /// with custom IO.
+  pub fn from_contexts(
+    input_format_context: AVFormatContextInput,
+    output_format_context: AVFormatContextOutput,
+  ) -> Self {
+    let policies = input_format_context
+      .streams()
+      .iter()
+      .map(|stream| {
+        let codec_type = stream.codecpar().codec_type();
+        if codec_type.is_video()
+          || codec_type.is_audio()
+          || codec_type.is_subtitle()
+        {
+          StreamPolicy::Copy
+        } else {
+          StreamPolicy::Drop
+        }
+      })
+      .collect::<Vec<_>>();
+    Self {
+      filters: vec![None; policies.len()],
+      input_format_context,
+      output_format_context,
+      policies,
+      control: JobControl::new(),
+    }
+  }
+
+  /// Get the inner [`AVFormatContextInput`].
+  pub fn input(&self) -> &AVFormatContextInput {
+    &self.input_format_context
+  }
+
+  /// Get the policy of stream `stream_index`.
+  pub fn policy(&self, stream_index: usize) -> Option<&StreamPolicy> {
+    self.policies.get(stream_index)
+  }
+
+  /// Set the policy of stream `stream_index`.
+  pub fn set_policy(
+    &mut self,
+    stream_index: usize,
+    policy: StreamPolicy,
+  ) -> Result<()> {
+    let slot = self
+      .policies
+      .get_mut(stream_index)
+      .ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))?;
+    *slot = policy;
+    Ok(())
+  }
+
+  /// Set the filter description of stream `stream_index`, e.g.
+  /// `scale=640:-2` or `volume=0.5`, which is applied if the stream is
+  /// re-encoded. The description is a single chain of filters with one
+  /// input and one output, `None` passes frames unchanged.
+  pub fn set_filter(
+    &mut self,
+    stream_index: usize,
+    filter: Option<&CStr>,
+  ) -> Result<()> {
+    let slot = self
+      .filters
+      .get_mut(stream_index)
+      .ok_or(RsmpegError::AVError(ffi::AVERROR_STREAM_NOT_FOUND))?;
+    *slot = filter.map(CStr::to_owned);
+    Ok(())
+  }
+
+  /// Get the [`JobControl`], which reports the progress and cancels the
+  /// transcoding.
+  pub fn control_mut(&mut self) -> &mut JobControl {
+    &mut self.control
+  }
+
+  /// Set the callback called with the progress after each packet read from
+  /// the input is processed, see [`JobControl::set_progress_callback`].
+  pub fn set_progress_callback(
+    &mut self,
+    callback: impl FnMut(&Progress) + Send + 'static,
+  ) {
+    self.control.set_progress_callback(callback);
+  }
+
+  /// Run the transcoding to the end of the input and write the trailer.
+  ///
+  /// If the [`JobControl`] is cancelled, the transcoding stops, the
+  /// encoders are flushed and the trailer is written, then
+  /// [`RsmpegError::Interrupted`] is returned.
+  pub fn run(mut self) -> Result<()> {
+    let mut stream_contexts = self.open_streams()?;
+    self.output_format_context.write_header(&mut None)?;
+
+    let start_time = match self.input_format_context.start_time {
+      ffi::AV_NOPTS_VALUE => 0,
+      start_time => start_time,
+    };
+    let mut output = Output {
+      format_context: self.output_format_context,
+      tracker: ProgressTracker::new(
+        start_time,
+        self.input_format_context.duration,
+      ),
+    };
+
+    let mut cancelled = false;
+    loop {
+      if self.control.is_cancelled() {
+        cancelled = true;
+        break;
+      }
+      let Some(mut packet) = self.input_format_context.read_packet()? else {
+        break;
+      };
+      let in_stream_index = packet.stream_index as usize;
+      let in_time_base =
+        self.input_format_context.streams()[in_stream_index].time_base;
+
+      match stream_contexts.get_mut(in_stream_index) {
+        Some(StreamContext::Copied { stream_index }) => {
+          packet.rescale_ts(
+            in_time_base,
+            output.format_context.streams()[*stream_index].time_base,
+          );
+          packet.set_stream_index(*stream_index as i32);
+          packet.set_pos(-1);
+          output.write(&mut packet)?;
+        }
+        Some(StreamContext::Reencoded(context)) => {
+          context.decode_context.send_packet(Some(&packet))?;
+          context.decode_frames(&mut output)?;
+        }
+        Some(StreamContext::Dropped) | None => {}
+      }
+      output.tracker.report(&mut self.control);
+    }
+
+    // Flush decoders, filters and encoders.
+    for context in &mut stream_contexts {
+      if let StreamContext::Reencoded(context) = context {
+        context.decode_context.send_packet(None)?;
+        context.decode_frames(&mut output)?;
+        context.filter_encode_write_frame(None, &mut output)?;
+        context.encode_write_frame(None, &mut output)?;
+      }
+    }
+
+    output.format_context.write_trailer()?;
+    output.tracker.report(&mut self.control);
+    if cancelled {
+      Err(RsmpegError::Interrupted)
+    } else {
+      Ok(())
+    }
+  }
+
+  /// Create the output streams and the codec contexts of every input
+  /// stream.
+  fn open_streams(&mut self) -> Result<Vec<StreamContext>> {
+    let global_header = self.output_format_context.oformat().flags
+      & ffi::AVFMT_GLOBALHEADER as i32
+      != 0;
+    let mut stream_contexts = vec![];
+    for (index, stream) in
+      self.input_format_context.streams().iter().enumerate()
+    {
+      let stream_context = match &self.policies[index] {
+        StreamPolicy::Drop => StreamContext::Dropped,
+        StreamPolicy::Copy => {
+          let mut codecpar = stream.codecpar().clone();
+          // The tag of the input container may be invalid in the output one.
+          codecpar.set_codec_tag(0);
+          let mut out_stream = self.output_format_context.new_stream();
+          out_stream.set_codecpar(codecpar);
+          out_stream.set_time_base(stream.time_base);
+          StreamContext::Copied {
+            stream_index: out_stream.index as usize,
+          }
+        }
+        StreamPolicy::Reencode(config) => {
+          let (decode_context, filter_graph, encode_context) = open_reencode(
+            stream,
+            config,
+            self.filters[index].as_deref(),
+            global_header,
+          )?;
+          let mut out_stream = self.output_format_context.new_stream();
+          out_stream.set_codecpar(encode_context.extract_codecpar());
+          out_stream.set_time_base(encode_context.time_base);
+          StreamContext::Reencoded(Box::new(ReencodeContext {
+            decode_context,
+            filter_graph,
+            encode_context,
+            stream_index: out_stream.index as usize,
+          }))
+        }
+      };
+      stream_contexts.push(stream_context);
+    }
+    Ok(stream_contexts)
+  }
+}
+
+impl ReencodeContext {
+  /// decode -> filter -> encode -> write_frame, for the frames the decoder
+  /// has produced.
+  fn decode_frames(&mut self, output: &mut Output) -> Result<()> {
+    loop {
+      let mut frame = match self.decode_context.receive_frame() {
+        Ok(frame) => frame,
+        Err(RsmpegError::DecoderDrainError)
+        | Err(RsmpegError::DecoderFlushedError) => return Ok(()),
+        Err(e) => return Err(e),
+      };
+      frame.set_pts(frame.best_effort_timestamp);
+      self.filter_encode_write_frame(Some(frame), output)?;
+    }
+  }
+
+  /// filter -> encode -> write_frame, `None` flushes the filter graph.
+  fn filter_encode_write_frame(
+    &mut self,
+    frame: Option<AVFrame>,
+    output: &mut Output,
+  ) -> Result<()> {
+    self
+      .filter_graph
+      .get_filter(name(BUFFERSRC_NAME))
+      .unwrap()
+      .buffersrc_add_frame(frame, None)?;
+    loop {
+      let filtered_frame = {
+        let mut buffersink_context =
+          self.filter_graph.get_filter(name(BUFFERSINK_NAME)).unwrap();
+        let mut filtered_frame =
+          match buffersink_context.buffersink_get_frame(None) {
+            Ok(frame) => frame,
+            Err(RsmpegError::BufferSinkDrainError)
+            | Err(RsmpegError::BufferSinkEofError) => return Ok(()),
+            Err(e) => return Err(e),
+          };
+        filtered_frame.set_time_base(buffersink_context.get_time_base());
+        filtered_frame.set_pict_type(ffi::AV_PICTURE_TYPE_NONE);
+        filtered_frame
+      };
+      self.encode_write_frame(Some(filtered_frame), output)?;
+    }
+  }
+
+  /// encode -> write_frame, `None` flushes the encoder.
+  fn encode_write_frame(
+    &mut self,
+    mut frame: Option<AVFrame>,
+    output: &mut Output,
+  ) -> Result<()> {
+    if let Some(frame) = frame.as_mut() {
+      output.tracker.frame_encoded();
+      if frame.pts != ffi::AV_NOPTS_VALUE {
+        frame.set_pts(av_rescale_q(
+          frame.pts,
+          frame.time_base,
+          self.encode_context.time_base,
+        ));
+      }
+    }
+    match self.encode_context.send_frame(frame.as_ref()) {
+      // Encoders without `AV_CODEC_CAP_DELAY` may be flushed already.
+      Ok(()) | Err(RsmpegError::EncoderFlushedError) => {}
+      Err(e) => return Err(e),
+    }
+
+    loop {
+      let mut packet = match self.encode_context.receive_packet() {
+        Ok(packet) => packet,
+        Err(RsmpegError::EncoderDrainError)
+        | Err(RsmpegError::EncoderFlushedError) => return Ok(()),
+        Err(e) => return Err(e),
+      };
+      packet.set_stream_index(self.stream_index as i32);
+      packet.rescale_ts(
+        self.encode_context.time_base,
+        output.format_context.streams()[self.stream_index].time_base,
+      );
+      output.write(&mut packet)?;
+    }
+  }
+}
+
+impl Output {
+  /// Write a packet whose stream index and timestamps are already the
+  /// output ones.
+  fn write(&mut self, packet: &mut AVPacket) -> Result<()> {
+    let time_base =
+      self.format_context.streams()[packet.stream_index as usize].time_base;
+    self.tracker.packet_written(packet, time_base);
+    self.format_context.interleaved_write_frame(packet)
+  }
+}
+
+/// Open the decoder, filter graph and encoder of a re-encoded stream.
+fn open_reencode(
+  stream: &AVStreamRef,
+  config: &EncoderConfig,
+  filter: Option<&CStr>,
+  global_header: bool,
+) -> Result<(AVCodecContext, AVFilterGraph, AVCodecContext)> {
+  let codecpar = stream.codecpar();
+  let codec_type = codecpar.codec_type();
+  if !(codec_type.is_video() || codec_type.is_audio()) {
+    return Err(RsmpegError::CustomError(format!(
+      "Stream #{} can't be re-encoded, only video and audio can.",
+      stream.index
+    )));
+  }
+
+  let decoder = AVCodec::find_decoder(codecpar.codec_id)
+    .ok_or(RsmpegError::AVError(ffi::AVERROR_DECODER_NOT_FOUND))?;
+  let mut decode_context = AVCodecContext::new(&decoder);
+  decode_context.apply_codecpar(&codecpar)?;
+  decode_context.set_pkt_timebase(stream.time_base);
+  if codec_type.is_video() {
+    if let Some(framerate) = stream.guess_framerate() {
+      decode_context.set_framerate(framerate);
+    }
+  }
+  decode_context.open(None)?;
+
+  let encoder = match &config.encoder {
+    Some(name) => AVCodec::find_encoder_by_name(name),
+    None => AVCodec::find_encoder(config.codec_id.unwrap_or(codecpar.codec_id)),
+  }
+  .ok_or(RsmpegError::AVError(ffi::AVERROR_ENCODER_NOT_FOUND))?;
+
+  let mut filter_graph =
+    open_filter_graph(&mut decode_context, &encoder, config, filter)?;
+
+  let mut encode_context = AVCodecContext::new(&encoder);
+  {
+    let mut buffersink_context =
+      filter_graph.get_filter(name(BUFFERSINK_NAME)).unwrap();
+    if codec_type.is_video() {
+      encode_context.set_width(buffersink_context.get_w());
+      encode_context.set_height(buffersink_context.get_h());
+      encode_context.set_pix_fmt(buffersink_context.get_format());
+      encode_context
+        .set_sample_aspect_ratio(buffersink_context.get_sample_aspect_ratio());
+      let frame_rate = buffersink_context.get_frame_rate();
+      if frame_rate.num > 0 && frame_rate.den > 0 {
+        encode_context.set_framerate(frame_rate);
+        encode_context.set_time_base(av_inv_q(frame_rate));
+      } else {
+        encode_context.set_time_base(buffersink_context.get_time_base());
+      }
+    } else {
+      let sample_rate = buffersink_context.get_sample_rate();
+      encode_context.set_sample_rate(sample_rate);
+      encode_context
+        .set_ch_layout(buffersink_context.get_ch_layout().into_inner());
+      encode_context.set_sample_fmt(buffersink_context.get_format());
+      encode_context.set_time_base(ra(1, sample_rate));
+    }
+    if let Some(bit_rate) = config.bit_rate {
+      encode_context.set_bit_rate(bit_rate);
+    }
+    if let Some(gop_size) = config.gop_size {
+      encode_context.set_gop_size(gop_size);
+    }
+    if let Some(max_b_frames) = config.max_b_frames {
+      encode_context.set_max_b_frames(max_b_frames);
+    }
+    if global_header {
+      encode_context.set_flags(
+        encode_context.flags | ffi::AV_CODEC_FLAG_GLOBAL_HEADER as i32,
+      );
+    }
+    let options = config.options.iter().fold(
+      None,
+      |dict: Option<AVDictionary>, (key, value)| {
+        Some(match dict {
+          None => AVDictionary::new(key, value, 0),
+          Some(dict) => dict.set(key, value, 0),
+        })
+      },
+    );
+    encode_context.open(options)?;
+
+    // Encoders with a fixed frame size, e.g. AAC, need frames of exactly
+    // that many samples.
+    if codec_type.is_audio()
+      && encode_context.frame_size > 0
+      && encoder.capabilities & ffi::AV_CODEC_CAP_VARIABLE_FRAME_SIZE as i32
+        == 0
+    {
+      unsafe {
+        ffi::av_buffersink_set_frame_size(
+          buffersink_context.as_mut_ptr(),
+          encode_context.frame_size as u32,
+        )
+      };
+    }
+  }
+
+  Ok((decode_context, filter_graph, encode_context))
+}
+
+/// Create the filter graph of a re-encoded stream, the output of `filter`
+/// is converted to a format `encoder` supports.
+fn open_filter_graph(
+  decode_context: &mut AVCodecContext,
+  encoder: &AVCodecRef,
+  config: &EncoderConfig,
+  filter: Option<&CStr>,
+) -> Result<AVFilterGraph> {
+  let filter_graph = AVFilterGraph::new();
+  let time_base = decode_context.pkt_timebase;
+  let is_video = decode_context.codec_type == ffi::AVMEDIA_TYPE_VIDEO;
+
+  let mut spec = match filter {
+    Some(filter) => filter.to_string_lossy().into_owned(),
+    None if is_video => "null".to_string(),
+    None => "anull".to_string(),
+  };
+  let (buffersrc, buffersink, args): (&[u8], &[u8], _) = if is_video {
+    let pix_fmts = match config.format {
+      Some(format) => Some(vec![format]),
+      None => encoder.pix_fmts().map(<[_]>::to_vec),
+    };
+    if let Some(pix_fmts) = pix_fmts {
+      spec += &format!(",format=pix_fmts={}", join(&pix_fmts));
+    }
+    let args = format!(
+      "video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{}",
+      decode_context.width,
+      decode_context.height,
+      decode_context.pix_fmt,
+      time_base.num,
+      time_base.den,
+      decode_context.sample_aspect_ratio.num,
+      // A zero denominator is rejected.
+      decode_context.sample_aspect_ratio.den.max(1),
+    );
+    (b"buffer\0", b"buffersink\0", args)
+  } else {
+    let sample_fmts = match config.format {
+      Some(format) => Some(vec![format]),
+      None => encoder.sample_fmts().map(<[_]>::to_vec),
+    };
+    let mut aformat = vec![];
+    if let Some(sample_fmts) = sample_fmts {
+      aformat.push(format!("sample_fmts={}", join(&sample_fmts)));
+    }
+    if let Some(sample_rates) = encoder.supported_samplerates() {
+      aformat.push(format!("sample_rates={}", join(sample_rates)));
+    }
+    if !aformat.is_empty() {
+      spec += &format!(",aformat={}", aformat.join(":"));
+    }
+
+    if decode_context.ch_layout.order == ffi::AV_CHANNEL_ORDER_UNSPEC {
+      decode_context.set_ch_layout(
+        AVChannelLayout::from_nb_channels(decode_context.ch_layout.nb_channels)
+          .into_inner(),
+      );
+    }
+    let args = format!(
+      "time_base={}/{}:sample_rate={}:sample_fmt={}:channel_layout={}",
+      time_base.num,
+      time_base.den,
+      decode_context.sample_rate,
+      decode_context.sample_fmt,
+      decode_context.ch_layout().describe()?.to_string_lossy(),
+    );
+    (b"abuffer\0", b"abuffersink\0", args)
+  };
+
+  let buffersrc = AVFilter::get_by_name(name(buffersrc))?;
+  let buffersink = AVFilter::get_by_name(name(buffersink))?;
+  let args = CString::new(args).unwrap();
+  let spec =
+    CString::new(spec).map_err(|e| RsmpegError::CustomError(e.to_string()))?;
+  {
+    let mut buffersrc_context = filter_graph.create_filter_context(
+      &buffersrc,
+      name(BUFFERSRC_NAME),
+      Some(&args),
+    )?;
+    let mut buffersink_context = filter_graph.create_filter_context(
+      &buffersink,
+      name(BUFFERSINK_NAME),
+      None,
+    )?;
+
+    // The outputs of the source are the inputs of the parsed graph.
+    let outputs =
+      AVFilterInOut::new(name(BUFFERSRC_NAME), &mut buffersrc_context, 0);
+    let inputs =
+      AVFilterInOut::new(name(BUFFERSINK_NAME), &mut buffersink_context, 0);
+    filter_graph.parse_ptr(&spec, Some(inputs), Some(outputs))?;
+  }
+  filter_graph.config()?;
+  Ok(filter_graph)
+}
+
+/// Join values with `|`, the list separator of filter options.
+fn join<T: ToString>(values: &[T]) -> String {
+  values
+    .iter()
+    .map(ToString::to_string)
+    .collect::<Vec<_>>()
+    .join("|")
+}
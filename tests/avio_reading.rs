@@ -6,7 +6,7 @@ use rs_ffmpeg::{
   avutil::{AVMem, AVMmap},
   ffi,
 };
-use std::ffi::CStr;
+use std::{ffi::CStr, fs::File, io::Cursor};
 
 fn avio_reading(filename: &CStr) -> Result<()> {
   let mmap = AVMmap::new(filename)?;
@@ -47,3 +47,30 @@ fn test_avio_reading0() {
 fn test_avio_reading1() {
   avio_reading(cstr!("tests/assets/vids/centaur.mpg")).unwrap();
 }
+
+fn avio_reading_from_reader(filename: &CStr) -> Result<()> {
+  let file = File::open(filename.to_str()?)?;
+  let mut input_format_context = AVFormatContextInput::from_io_context(
+    AVIOContextContainer::Custom(AVIOContextCustom::from_reader(file)),
+  )?;
+  input_format_context.dump(0, filename)?;
+
+  let bytes = std::fs::read(filename.to_str()?)?;
+  let mut input_format_context =
+    AVFormatContextInput::from_io_context(AVIOContextContainer::Custom(
+      AVIOContextCustom::from_reader(Cursor::new(bytes)),
+    ))?;
+  while input_format_context.read_packet()?.is_some() {}
+
+  Ok(())
+}
+
+#[test]
+fn test_avio_reading_from_reader0() {
+  avio_reading_from_reader(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+}
+
+#[test]
+fn test_avio_reading_from_reader1() {
+  avio_reading_from_reader(cstr!("tests/assets/vids/centaur.mpg")).unwrap();
+}
@@ -12,11 +12,12 @@ use rs_ffmpeg::{
   avutil::{av_inv_q, av_mul_q, AVFrame, AVMem, AVRational},
   error::RsmpegError,
   ffi::{self, AV_CODEC_ID_H264},
+  UnsafeDerefMut,
 };
 use std::{
   ffi::CStr,
   fs::File,
-  io::{Seek, SeekFrom, Write},
+  io::{Cursor, Seek, SeekFrom, Write},
   sync::{Arc, Mutex},
 };
 
@@ -299,3 +300,88 @@ fn avio_writing_test5() {
   )
   .unwrap();
 }
+
+/// In-memory output shared with the custom IO context, so the muxed bytes can
+/// be inspected after the output context is done.
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Cursor<Vec<u8>>>>);
+
+impl Write for SharedBuffer {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.0.lock().unwrap().write(buf)
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
+
+impl Seek for SharedBuffer {
+  fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+    self.0.lock().unwrap().seek(pos)
+  }
+}
+
+/// Remux the given file into an in-memory buffer, return the muxed bytes.
+fn remux_to_memory(input_file: &CStr, format_name: &CStr) -> Result<Vec<u8>> {
+  let mut input_format_context =
+    AVFormatContextInput::open(input_file, None, &mut None)?;
+
+  let buffer = SharedBuffer::default();
+  let mut output_format_context = AVFormatContextOutput::from_io_context(
+    AVIOContextContainer::Custom(AVIOContextCustom::from_writer(
+      buffer.clone(),
+    )),
+    format_name,
+  )?;
+
+  for stream in input_format_context.streams() {
+    let mut codecpar = stream.codecpar().clone();
+    unsafe { codecpar.deref_mut().codec_tag = 0 };
+    output_format_context.new_stream().set_codecpar(codecpar);
+  }
+  output_format_context.write_header(&mut None)?;
+
+  while let Some(mut packet) = input_format_context.read_packet()? {
+    let index = packet.stream_index as usize;
+    packet.rescale_ts(
+      input_format_context.streams()[index].time_base,
+      output_format_context.streams()[index].time_base,
+    );
+    packet.set_pos(-1);
+    output_format_context.interleaved_write_frame(&mut packet)?;
+  }
+  output_format_context.write_trailer()?;
+  drop(output_format_context);
+
+  let data = buffer.0.lock().unwrap().get_ref().clone();
+  Ok(data)
+}
+
+#[test]
+fn avio_writing_custom_output_test0() {
+  let data =
+    remux_to_memory(cstr!("tests/assets/vids/bear.mp4"), cstr!("mp4")).unwrap();
+  assert!(!data.is_empty());
+
+  let input_format_context =
+    AVFormatContextInput::from_io_context(AVIOContextContainer::Custom(
+      AVIOContextCustom::from_reader(Cursor::new(data)),
+    ))
+    .unwrap();
+  assert_eq!(input_format_context.streams().len(), 2);
+}
+
+#[test]
+fn avio_writing_custom_output_test1() {
+  // Unknown muxer.
+  assert!(matches!(
+    AVFormatContextOutput::from_io_context(
+      AVIOContextContainer::Custom(AVIOContextCustom::from_writer(
+        SharedBuffer::default()
+      )),
+      cstr!("__random__"),
+    ),
+    Err(RsmpegError::OpenOutputError(_))
+  ));
+}
@@ -0,0 +1,98 @@
+//! Concatenate inputs by stream copy with `concat_copy`.
+use cstr::cstr;
+use rs_ffmpeg::{
+  avformat::{AVFormatContextInput, CancellationToken},
+  error::RsmpegError,
+  tools::{concat_copy, concat_copy_with, JobControl},
+};
+use std::{
+  ffi::CStr,
+  sync::{Arc, Mutex},
+};
+
+fn open(path: &CStr) -> AVFormatContextInput {
+  AVFormatContextInput::open(path, None, &mut None).unwrap()
+}
+
+/// Count packets of each stream.
+fn count_packets(path: &CStr) -> Vec<usize> {
+  let mut input_format_context = open(path);
+  let nb_streams = input_format_context.streams().len();
+  let mut counts = vec![0; nb_streams];
+  let mut last_dts = vec![i64::MIN; nb_streams];
+  for packet in input_format_context.packets() {
+    let packet = packet.unwrap();
+    let index = packet.stream_index as usize;
+    counts[index] += 1;
+    // Timestamps keep increasing across the inputs.
+    assert!(packet.dts > last_dts[index]);
+    last_dts[index] = packet.dts;
+  }
+  counts
+}
+
+#[test]
+fn concat_test0() {
+  std::fs::create_dir_all("tests/output/concat/").unwrap();
+  let input = cstr!("tests/assets/vids/bear.mp4");
+  let output = cstr!("tests/output/concat/bear_twice.mp4");
+  concat_copy(&[input, input], output).unwrap();
+
+  let expected = count_packets(input);
+  let counts = count_packets(output);
+  assert_eq!(counts, expected.iter().map(|x| x * 2).collect::<Vec<_>>());
+
+  let input_duration = open(input).duration;
+  let output_duration = open(output).duration;
+  assert!((output_duration - 2 * input_duration).abs() < 100_000);
+}
+
+#[test]
+fn concat_test1() {
+  let error = concat_copy(
+    &[
+      cstr!("tests/assets/vids/bear.mp4"),
+      cstr!("tests/assets/vids/centaur.mpg"),
+    ],
+    cstr!("tests/output/concat/never_written.mp4"),
+  )
+  .unwrap_err();
+  let RsmpegError::IncompatibleStreamsError(message) = error else {
+    panic!("Unexpected error: {error}");
+  };
+  assert!(message.contains("input 1"));
+  assert!(message.contains("codec_id"));
+}
+
+/// Cancel from the progress callback, the output is still readable.
+#[test]
+fn concat_test2() {
+  std::fs::create_dir_all("tests/output/concat/").unwrap();
+  let input = cstr!("tests/assets/vids/bear.mp4");
+  let output = cstr!("tests/output/concat/bear_cancelled.mp4");
+
+  let token = CancellationToken::new();
+  let last = Arc::new(Mutex::new(None));
+  let mut control = JobControl::new();
+  control.set_cancellation_token(token.clone());
+  {
+    let last = last.clone();
+    control.set_progress_callback(move |x| {
+      if x.packets == 20 {
+        token.cancel();
+      }
+      *last.lock().unwrap() = Some(*x);
+    });
+  }
+  let error =
+    concat_copy_with(&[input, input], output, &mut control).unwrap_err();
+  assert!(matches!(error, RsmpegError::Interrupted));
+
+  let last = last.lock().unwrap().unwrap();
+  assert_eq!(last.packets, 20);
+  assert_eq!(last.frames, 0);
+  assert!(last.fraction().unwrap() < 0.5);
+
+  let counts = count_packets(output);
+  assert_eq!(counts.iter().sum::<usize>(), 20);
+}
@@ -0,0 +1,90 @@
+//! Attach cover art to an audio file, then extract it back.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rs_ffmpeg::{
+  avformat::{AVFormatContextInput, AVFormatContextOutput, Disposition},
+  ffi,
+};
+use std::ffi::CStr;
+
+/// Remux `audio_path` to `output_path` with the picture at `picture_path`
+/// as its cover art.
+fn add_cover_art(
+  audio_path: &CStr,
+  picture_path: &CStr,
+  output_path: &CStr,
+) -> Result<()> {
+  let mut input_format_context =
+    AVFormatContextInput::open(audio_path, None, &mut None)
+      .context("Open audio failed.")?;
+  let mut picture_format_context =
+    AVFormatContextInput::open(picture_path, None, &mut None)
+      .context("Open picture failed.")?;
+  let mut output_format_context =
+    AVFormatContextOutput::create(output_path, None)
+      .context("Create output format context failed.")?;
+
+  output_format_context
+    .new_stream()
+    .set_codecpar(input_format_context.streams()[0].codecpar().clone());
+
+  let picture = picture_format_context
+    .read_packet()?
+    .context("Picture is empty.")?;
+  output_format_context.add_cover_art(
+    picture_format_context.streams()[0].codecpar().clone(),
+    picture,
+  );
+
+  output_format_context
+    .write_header(&mut None)
+    .context("Write header failed.")?;
+  while let Some(mut packet) = input_format_context.read_packet()? {
+    if packet.stream_index != 0 {
+      continue;
+    }
+    packet.rescale_ts(
+      input_format_context.streams()[0].time_base,
+      output_format_context.streams()[0].time_base,
+    );
+    packet.set_pos(-1);
+    output_format_context
+      .interleaved_write_frame(&mut packet)
+      .context("Interleaved write frame failed.")?;
+  }
+  output_format_context
+    .write_trailer()
+    .context("Write trailer failed.")
+}
+
+#[test]
+fn cover_art_test0() {
+  std::fs::create_dir_all("tests/output/cover_art/").unwrap();
+  let output_path = cstr!("tests/output/cover_art/sample1_short.m4a");
+  add_cover_art(
+    cstr!("tests/assets/audios/sample1_short.aac"),
+    cstr!("tests/assets/pics/bear.jpg"),
+    output_path,
+  )
+  .unwrap();
+
+  let input_format_context =
+    AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+  let cover_art = input_format_context
+    .streams()
+    .iter()
+    .find(|stream| stream.disposition().contains(Disposition::ATTACHED_PIC))
+    .unwrap();
+  assert_eq!(cover_art.codecpar().codec_id, ffi::AV_CODEC_ID_MJPEG);
+
+  let picture = cover_art.attached_pic().unwrap();
+  let expected = std::fs::read("tests/assets/pics/bear.jpg").unwrap();
+  assert_eq!(picture.size as usize, expected.len());
+
+  let audio = input_format_context
+    .streams()
+    .iter()
+    .find(|stream| stream.codecpar().codec_type().is_audio())
+    .unwrap();
+  assert!(audio.attached_pic().is_none());
+}
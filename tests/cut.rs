@@ -0,0 +1,129 @@
+//! Cut a range of a video with `cut`.
+use cstr::cstr;
+use rs_ffmpeg::{
+  avcodec::{AVCodec, AVCodecContext},
+  avformat::AVFormatContextInput,
+  avutil::av_rescale_q,
+  error::RsmpegError,
+  ffi,
+  tools::{cut, CutMode},
+};
+use std::{ffi::CStr, time::Duration};
+
+/// Timestamp of the first packet in `AV_TIME_BASE` units and number of
+/// decoded frames of the best video stream of `path`, every packet must
+/// decode without error.
+fn decode_video(path: &CStr) -> (i64, usize) {
+  let mut input_format_context =
+    AVFormatContextInput::open(path, None, &mut None).unwrap();
+  let (video_index, decoder) = input_format_context
+    .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+    .unwrap()
+    .unwrap();
+  let stream = &input_format_context.streams()[video_index];
+  let time_base = stream.time_base;
+  let mut decode_context = AVCodecContext::new(&decoder);
+  decode_context.apply_codecpar(&stream.codecpar()).unwrap();
+  decode_context.open(None).unwrap();
+
+  let mut first_pts = None;
+  let mut frames = 0;
+  loop {
+    let packet = loop {
+      match input_format_context.read_packet().unwrap() {
+        Some(x) if x.stream_index != video_index as i32 => {}
+        x => break x,
+      }
+    };
+    if let Some(packet) = &packet {
+      // The output starts with a keyframe.
+      if first_pts.is_none() {
+        assert_ne!(packet.flags & ffi::AV_PKT_FLAG_KEY as i32, 0);
+        first_pts = Some(packet.pts);
+      }
+    }
+    decode_context.send_packet(packet.as_ref()).unwrap();
+    loop {
+      match decode_context.receive_frame() {
+        Ok(_) => frames += 1,
+        Err(RsmpegError::DecoderDrainError) => break,
+        Err(RsmpegError::DecoderFlushedError) => {
+          let start =
+            av_rescale_q(first_pts.unwrap(), time_base, ffi::AV_TIME_BASE_Q);
+          return (start, frames);
+        }
+        Err(e) => panic!("{e}"),
+      }
+    }
+  }
+}
+
+fn duration(path: &CStr) -> Duration {
+  let input_format_context =
+    AVFormatContextInput::open(path, None, &mut None).unwrap();
+  Duration::from_micros(input_format_context.duration as u64)
+}
+
+/// Snapping to keyframes never cuts less than requested.
+#[test]
+fn cut_test0() {
+  std::fs::create_dir_all("tests/output/cut/").unwrap();
+  let output = cstr!("tests/output/cut/centaur_snap.mkv");
+  cut(
+    cstr!("tests/assets/vids/centaur.mpg"),
+    output,
+    Duration::from_millis(1000),
+    Duration::from_millis(2000),
+    CutMode::KeyframeSnap,
+  )
+  .unwrap();
+
+  let (start, frames) = decode_video(output);
+  assert_eq!(start, 0);
+  assert!(frames > 0);
+  assert!(duration(output) >= Duration::from_millis(1000));
+}
+
+/// Re-encoding the edges keeps the exact range.
+#[test]
+fn cut_test1() {
+  std::fs::create_dir_all("tests/output/cut/").unwrap();
+  let output = cstr!("tests/output/cut/centaur_smart.mkv");
+  cut(
+    cstr!("tests/assets/vids/centaur.mpg"),
+    output,
+    Duration::from_millis(1100),
+    Duration::from_millis(2100),
+    CutMode::SmartReencodeEdges,
+  )
+  .unwrap();
+
+  let (start, frames) = decode_video(output);
+  assert!(start < 50_000);
+  assert!(frames > 0);
+  let duration = duration(output);
+  assert!(duration >= Duration::from_millis(950));
+  assert!(duration <= Duration::from_millis(1150));
+}
+
+/// The copied H.264 GOPs and the re-encoded ones are decodable together.
+#[test]
+fn cut_test2() {
+  assert!(
+    AVCodec::find_encoder(ffi::AV_CODEC_ID_H264).is_some(),
+    "An H.264 encoder is needed to re-encode the edges."
+  );
+  std::fs::create_dir_all("tests/output/cut/").unwrap();
+  let output = cstr!("tests/output/cut/bear_smart.mp4");
+  cut(
+    cstr!("tests/assets/vids/bear.mp4"),
+    output,
+    Duration::from_millis(300),
+    Duration::from_millis(1300),
+    CutMode::SmartReencodeEdges,
+  )
+  .unwrap();
+
+  let (_, frames) = decode_video(output);
+  assert!(frames > 0);
+}
@@ -5,8 +5,8 @@ use rs_ffmpeg::{
   avcodec::{AVCodecContext, AVCodecParserContext, AVPacket},
   avformat::AVFormatContextInput,
   avutil::{
-    get_bytes_per_sample, get_packed_sample_fmt, get_sample_fmt_name,
-    sample_fmt_is_planar, AVFrame, AVSampleFormat,
+    get_packed_sample_fmt, get_sample_fmt_name, sample_fmt_is_planar, AVFrame,
+    AVSampleFormat, SampleType,
   },
   error::RsmpegError,
   ffi,
@@ -16,7 +16,6 @@ use std::{
   fs::{self, File},
   io::{Read, Write},
   path::Path,
-  slice::from_raw_parts,
 };
 
 fn get_format_from_sample_fmt(
@@ -35,23 +34,27 @@ fn get_format_from_sample_fmt(
     .map(|(_, fmt)| *fmt)
 }
 
-fn frame_save(
+/// Write the samples of `frame` interleaved, each encoded by `to_bytes`.
+fn frame_save<T: SampleType, const N: usize>(
   frame: &AVFrame,
-  channels: usize,
-  data_size: usize,
+  to_bytes: fn(T) -> [u8; N],
   mut file: &File,
 ) -> Result<()> {
-  let nb_samples: usize =
-    frame.nb_samples.try_into().context("nb_samples overflow")?;
-  // ATTENTION: This is only valid for planar sample formats.
-  for i in 0..nb_samples {
-    for channel in 0..channels {
-      let data = unsafe {
-        from_raw_parts(frame.data[channel].add(data_size * i), data_size)
-      };
-      file.write_all(data).context("Write data failed.")?;
+  let mut data = vec![];
+  if let Some(planes) = frame.planes::<T>() {
+    let nb_samples = planes.first().map_or(0, |plane| plane.len());
+    for i in 0..nb_samples {
+      for plane in &planes {
+        data.extend(to_bytes(plane[i]));
+      }
+    }
+  } else {
+    let samples = frame.samples::<T>().context("Unexpected sample fmt")?;
+    for &sample in samples {
+      data.extend(to_bytes(sample));
     }
   }
+  file.write_all(&data).context("Write data failed.")?;
   Ok(())
 }
 
@@ -63,12 +66,8 @@ fn decode(
   decode_context
     .send_packet(packet)
     .context("Send packet failed.")?;
-  let channels = decode_context
-    .ch_layout
-    .nb_channels
-    .try_into()
-    .context("channels overflow")?;
-  let sample_fmt = decode_context.sample_fmt;
+  let sample_fmt = get_packed_sample_fmt(decode_context.sample_fmt)
+    .context("Unknown sample fmt")?;
   loop {
     let frame = match decode_context.receive_frame() {
       Ok(frame) => frame,
@@ -76,9 +75,14 @@ fn decode(
       | Err(RsmpegError::DecoderFlushedError) => break,
       Err(e) => return Err(e).context("Receive frame failed."),
     };
-    let data_size =
-      get_bytes_per_sample(sample_fmt).context("Unknown sample fmt")?;
-    frame_save(&frame, channels, data_size, out_file)?;
+    match sample_fmt {
+      ffi::AV_SAMPLE_FMT_U8 => frame_save(&frame, u8::to_le_bytes, out_file)?,
+      ffi::AV_SAMPLE_FMT_S16 => frame_save(&frame, i16::to_le_bytes, out_file)?,
+      ffi::AV_SAMPLE_FMT_S32 => frame_save(&frame, i32::to_le_bytes, out_file)?,
+      ffi::AV_SAMPLE_FMT_FLT => frame_save(&frame, f32::to_le_bytes, out_file)?,
+      ffi::AV_SAMPLE_FMT_DBL => frame_save(&frame, f64::to_le_bytes, out_file)?,
+      _ => return Err(anyhow!("Unsupported sample fmt")),
+    }
   }
   Ok(())
 }
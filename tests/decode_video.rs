@@ -11,20 +11,13 @@ use rs_ffmpeg::{
 use std::{
   fs::{self, File},
   io::prelude::*,
-  slice,
 };
 
 /// Save a `AVFrame` as pgm file.
 fn pgm_save(frame: &AVFrame, filename: &str) -> Result<()> {
-  // Here we only capture the first layer of frame.
-  let data = frame.data[0];
-  let linesize = frame.linesize[0] as usize;
-
   let width = frame.width as usize;
   let height = frame.height as usize;
 
-  let buffer = unsafe { slice::from_raw_parts(data, linesize * height) };
-
   // Create pgm file
   let mut pgm_file = fs::File::create(filename)?;
 
@@ -32,9 +25,9 @@ fn pgm_save(frame: &AVFrame, filename: &str) -> Result<()> {
   pgm_file
     .write_all(&format!("P5\n{} {}\n{}\n", width, height, 255).into_bytes())?;
 
-  // Write pgm data
-  for i in 0..height {
-    pgm_file.write_all(&buffer[i * linesize..i * linesize + width])?;
+  // Write pgm data, here we only capture the first plane of frame.
+  for row in frame.rows(0).context("Frame has no luma plane")? {
+    pgm_file.write_all(row)?;
   }
 
   pgm_file.flush()?;
@@ -0,0 +1,53 @@
+//! Grab frames at exact timestamps with `FrameGrabber`.
+use cstr::cstr;
+use rs_ffmpeg::{avutil::av_rescale_q, ffi, tools::FrameGrabber};
+use std::time::Duration;
+
+#[test]
+fn frame_grabber_test0() {
+  let mut grabber =
+    FrameGrabber::open(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+
+  let frame = grabber.grab(Duration::from_millis(1000)).unwrap().unwrap();
+  // The returned frame is the one displayed at the requested timestamp.
+  let stream = &grabber.input().streams()[grabber.stream_index()];
+  let start_time = match stream.start_time {
+    ffi::AV_NOPTS_VALUE => 0,
+    start_time => start_time,
+  };
+  let requested =
+    start_time + av_rescale_q(1_000_000, ffi::AV_TIME_BASE_Q, stream.time_base);
+  assert!(frame.duration > 0);
+  assert!(frame.best_effort_timestamp <= requested);
+  assert!(requested < frame.best_effort_timestamp + frame.duration);
+
+  // A request later in the same GOP continues decoding from the current
+  // position instead of seeking back to the keyframe and decoding again.
+  let decoded = grabber.decode_context().frame_num;
+  assert!(decoded > 2);
+  let next_pts = frame.best_effort_timestamp + frame.duration;
+  let next = grabber.grab_pts(next_pts).unwrap().unwrap();
+  assert_eq!(next.best_effort_timestamp, next_pts);
+  assert!(grabber.decode_context().frame_num - decoded <= 2);
+
+  let later = grabber.grab(Duration::from_millis(1500)).unwrap().unwrap();
+  assert!(later.best_effort_timestamp > frame.best_effort_timestamp);
+
+  // Going backwards seeks again and returns the same frame.
+  let again = grabber.grab(Duration::from_millis(1000)).unwrap().unwrap();
+  assert_eq!(again.best_effort_timestamp, frame.best_effort_timestamp);
+}
+
+#[test]
+fn frame_grabber_test1() {
+  let mut grabber =
+    FrameGrabber::open(cstr!("tests/assets/vids/video.mp4")).unwrap();
+
+  let frame = grabber
+    .grab_scaled(Duration::from_millis(500), 160, 90, ffi::AV_PIX_FMT_RGB24)
+    .unwrap()
+    .unwrap();
+  assert_eq!(frame.width, 160);
+  assert_eq!(frame.height, 90);
+  assert_eq!(frame.format, ffi::AV_PIX_FMT_RGB24);
+}
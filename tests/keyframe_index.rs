@@ -0,0 +1,105 @@
+use cstr::cstr;
+use rs_ffmpeg::{avformat::AVFormatContextInput, ffi, tools::KeyframeIndex};
+use std::ffi::CStr;
+use tempdir::TempDir;
+
+fn open(path: &CStr) -> AVFormatContextInput {
+  AVFormatContextInput::open(path, None, &mut None).unwrap()
+}
+
+/// The container index of MP4 and a packet scan agree on keyframes.
+#[test]
+fn keyframe_index_test0() {
+  let path = cstr!("tests/assets/vids/bear.mp4");
+  let input_format_context = open(path);
+  let (video_index, _) = input_format_context
+    .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+    .unwrap()
+    .unwrap();
+  let from_container =
+    KeyframeIndex::from_container_index(&input_format_context).unwrap();
+  let scanned = KeyframeIndex::scan(&mut open(path)).unwrap();
+
+  let from_container = from_container.stream(video_index).unwrap();
+  let scanned = scanned.stream(video_index).unwrap();
+  assert!(!scanned.entries.is_empty());
+  assert_eq!(from_container.entries.len(), scanned.entries.len());
+  for (x, y) in from_container.entries.iter().zip(&scanned.entries) {
+    assert_eq!(x.pts, ffi::AV_NOPTS_VALUE);
+    assert_eq!(x.dts, y.dts);
+    assert_eq!(x.pos, y.pos);
+    assert_eq!(x.size, y.size);
+  }
+
+  let first = scanned.entries[0];
+  assert_eq!(scanned.keyframe_before(first.timestamp()), Some(&first));
+  assert_eq!(scanned.keyframe_before(first.timestamp() - 1), None);
+  assert_eq!(scanned.keyframe_after(i64::MIN), Some(&first));
+}
+
+/// Formats without an index are scanned, and the index is cached on disk.
+#[test]
+fn keyframe_index_test1() {
+  let path = cstr!("tests/assets/vids/centaur.mpg");
+  let dir = TempDir::new("keyframe_index").unwrap();
+  let cache_path = dir.path().join("centaur.index");
+
+  let index =
+    KeyframeIndex::load_or_build(&mut open(path), &cache_path).unwrap();
+  assert!(index.streams.iter().any(|x| !x.entries.is_empty()));
+
+  let mut serialized = vec![];
+  index.write_to(&mut serialized).unwrap();
+  assert_eq!(KeyframeIndex::read_from(&serialized[..]).unwrap(), index);
+
+  let input_format_context = open(path);
+  assert!(index.is_valid_for(&input_format_context));
+  assert!(!index.is_valid_for(&open(cstr!("tests/assets/vids/bear.mp4"))));
+
+  // Loaded from the cache this time.
+  let cached =
+    KeyframeIndex::load_or_build(&mut open(path), &cache_path).unwrap();
+  assert_eq!(cached, index);
+
+  assert!(KeyframeIndex::read_from(&b"garbage"[..]).is_err());
+}
+
+/// Corrupted caches are rejected and rebuilt.
+#[test]
+fn keyframe_index_test2() {
+  let path = cstr!("tests/assets/vids/centaur.mpg");
+  let dir = TempDir::new("keyframe_index").unwrap();
+  let cache_path = dir.path().join("centaur.index");
+  let index = KeyframeIndex::build(&mut open(path)).unwrap();
+  let mut serialized = vec![];
+  index.write_to(&mut serialized).unwrap();
+  let serialized = String::from_utf8(serialized).unwrap();
+
+  // Replace the field `field` of line `line`.
+  let corrupt = |line: usize, field: usize, value: &str| {
+    let mut lines: Vec<String> =
+      serialized.lines().map(str::to_string).collect();
+    let mut fields: Vec<&str> = lines[line].split(' ').collect();
+    fields[field] = value;
+    lines[line] = fields.join(" ");
+    lines.join("\n") + "\n"
+  };
+
+  for corrupted in [
+    // Stream count.
+    corrupt(1, 2, "-1"),
+    corrupt(1, 2, &i64::MAX.to_string()),
+    // Entry count, time base denominator and stream index of a stream.
+    corrupt(2, 3, "-1"),
+    corrupt(2, 3, &i64::MAX.to_string()),
+    corrupt(2, 2, "0"),
+    corrupt(2, 0, "-1"),
+  ] {
+    assert!(KeyframeIndex::read_from(corrupted.as_bytes()).is_err());
+
+    std::fs::write(&cache_path, &corrupted).unwrap();
+    let rebuilt =
+      KeyframeIndex::load_or_build(&mut open(path), &cache_path).unwrap();
+    assert_eq!(rebuilt, index);
+  }
+}
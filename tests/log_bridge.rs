@@ -0,0 +1,104 @@
+//! Forward FFmpeg log messages to the `log` crate with `log_set_bridge`.
+#![cfg(all(feature = "log", not(feature = "tracing")))]
+use cstr::cstr;
+use rs_ffmpeg::{
+  avcodec::{AVCodec, AVCodecContext},
+  avutil::{log_get_level, log_set_bridge, log_set_level, LogLevel},
+  ffi,
+};
+use std::{ffi::CString, ptr, sync::Mutex};
+
+/// Records as `(level, target, message)`.
+static RECORDS: Mutex<Vec<(log::Level, String, String)>> = Mutex::new(vec![]);
+
+struct Logger;
+
+impl log::Log for Logger {
+  fn enabled(&self, metadata: &log::Metadata) -> bool {
+    metadata.target().starts_with("ffmpeg")
+  }
+
+  fn log(&self, record: &log::Record) {
+    if self.enabled(record.metadata()) {
+      RECORDS.lock().unwrap().push((
+        record.level(),
+        record.target().to_string(),
+        record.args().to_string(),
+      ));
+    }
+  }
+
+  fn flush(&self) {}
+}
+
+#[test]
+fn log_bridge_test0() {
+  log::set_logger(&Logger).unwrap();
+  log::set_max_level(log::LevelFilter::Trace);
+  log_set_bridge();
+  log_set_level(LogLevel::Verbose);
+  assert_eq!(log_get_level(), LogLevel::Verbose);
+
+  let decoder = AVCodec::find_decoder(ffi::AV_CODEC_ID_H264).unwrap();
+  let decode_context = AVCodecContext::new(&decoder);
+  unsafe {
+    ffi::av_log(
+      ptr::null_mut(),
+      ffi::AV_LOG_WARNING as i32,
+      cstr!("frame %d\n").as_ptr(),
+      42,
+    );
+    // A line built with several calls is emitted once.
+    ffi::av_log(
+      decode_context.as_ptr() as *mut _,
+      ffi::AV_LOG_ERROR as i32,
+      cstr!("broken ").as_ptr(),
+    );
+    ffi::av_log(
+      decode_context.as_ptr() as *mut _,
+      ffi::AV_LOG_ERROR as i32,
+      cstr!("slice\n").as_ptr(),
+    );
+    ffi::av_log(
+      decode_context.as_ptr() as *mut _,
+      ffi::AV_LOG_VERBOSE as i32,
+      cstr!("verbose\n").as_ptr(),
+    );
+    // A message longer than the line buffer is truncated, not merged into
+    // the next one.
+    let long = CString::new("x".repeat(2000)).unwrap();
+    ffi::av_log(
+      ptr::null_mut(),
+      ffi::AV_LOG_INFO as i32,
+      cstr!("%s\n").as_ptr(),
+      long.as_ptr(),
+    );
+    ffi::av_log(
+      ptr::null_mut(),
+      ffi::AV_LOG_WARNING as i32,
+      cstr!("short\n").as_ptr(),
+    );
+    // Filtered by the FFmpeg level.
+    ffi::av_log(
+      ptr::null_mut(),
+      ffi::AV_LOG_DEBUG as i32,
+      cstr!("debug\n").as_ptr(),
+    );
+  }
+
+  let records = RECORDS.lock().unwrap();
+  assert_eq!(
+    *records,
+    [
+      (log::Level::Warn, "ffmpeg".into(), "frame 42".into()),
+      (
+        log::Level::Error,
+        "ffmpeg::h264".into(),
+        "broken slice".into()
+      ),
+      (log::Level::Debug, "ffmpeg::h264".into(), "verbose".into()),
+      (log::Level::Info, "ffmpeg".into(), "x".repeat(1023)),
+      (log::Level::Warn, "ffmpeg".into(), "short".into()),
+    ]
+  );
+}
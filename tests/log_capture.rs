@@ -0,0 +1,94 @@
+//! Attach the FFmpeg log lines of failed calls to errors with `LogCapture`.
+use cstr::cstr;
+use rs_ffmpeg::{
+  avcodec::{AVCodec, AVCodecContext},
+  avfilter::AVFilterGraph,
+  avutil::{log_set_callback, log_set_default_callback, LogCapture},
+  error::RsmpegError,
+  ffi,
+};
+use std::{
+  ffi::{c_char, c_int, c_void},
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[test]
+fn log_capture_test0() {
+  let encoder = AVCodec::find_encoder(ffi::AV_CODEC_ID_MPEG4).unwrap();
+
+  // Without capture, only the code is returned.
+  let mut encode_context = AVCodecContext::new(&encoder);
+  let err = encode_context.open(None).err().unwrap();
+  assert!(matches!(err, RsmpegError::CodecOpenError(_)));
+  assert!(err.log_lines().is_empty());
+
+  let _capture = LogCapture::new(4);
+  let mut encode_context = AVCodecContext::new(&encoder);
+  let err = encode_context.open(None).err().unwrap();
+  assert!(matches!(err.inner(), RsmpegError::CodecOpenError(_)));
+  assert_eq!(err.raw_error(), Some(ffi::AVERROR(ffi::EINVAL)));
+  let lines = err.log_lines();
+  assert!(!lines.is_empty() && lines.len() <= 4);
+  assert!(lines.iter().all(|line| line.starts_with("[mpeg4] ")));
+  let message = err.to_string();
+  assert!(message.starts_with("Failed to open codec. (-22): Invalid argument"));
+  assert!(message.contains("\n  [mpeg4] "));
+}
+
+#[test]
+fn log_capture_test1() {
+  let _capture = LogCapture::new(8);
+  let filter_graph = AVFilterGraph::new();
+  let err = filter_graph
+    .parse_ptr(cstr!("nope=1"), None, None)
+    .err()
+    .unwrap();
+  assert!(err
+    .log_lines()
+    .iter()
+    .any(|line| line.contains("No such filter: 'nope'")));
+
+  // Lines of earlier calls aren't attached.
+  let filter_graph = AVFilterGraph::new();
+  let err = filter_graph
+    .parse_ptr(cstr!("scale=foo=1"), None, None)
+    .err()
+    .unwrap();
+  assert!(!err.log_lines().is_empty());
+  assert!(err.log_lines().iter().all(|line| !line.contains("nope")));
+}
+
+static MESSAGES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe extern "C" fn count_messages(
+  _: *mut c_void,
+  _: c_int,
+  _: *const c_char,
+  _: ffi::va_list,
+) {
+  MESSAGES.fetch_add(1, Ordering::SeqCst);
+}
+
+#[test]
+fn log_capture_test2() {
+  // The callback still gets the messages while capturing.
+  log_set_callback(count_messages);
+  let capture = LogCapture::new(8);
+  let filter_graph = AVFilterGraph::new();
+  let err = filter_graph
+    .parse_ptr(cstr!("nope=1"), None, None)
+    .err()
+    .unwrap();
+  assert!(!err.log_lines().is_empty());
+  assert!(MESSAGES.load(Ordering::SeqCst) > 0);
+
+  // Restoring the default callback doesn't stop the capture.
+  log_set_default_callback();
+  let filter_graph = AVFilterGraph::new();
+  let err = filter_graph
+    .parse_ptr(cstr!("nope=1"), None, None)
+    .err()
+    .unwrap();
+  assert!(!err.log_lines().is_empty());
+  drop(capture);
+}
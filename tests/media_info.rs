@@ -0,0 +1,66 @@
+//! Structured `ffprobe`-like report with `MediaInfo`.
+use cstr::cstr;
+use rs_ffmpeg::{ffi, tools::MediaInfo};
+use std::time::Duration;
+
+#[test]
+fn media_info_test0() {
+  let info = MediaInfo::probe(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+
+  let format = &info.format;
+  assert_eq!(format.filename, "tests/assets/vids/bear.mp4");
+  assert_eq!(format.format_name, "mov,mp4,m4a,3gp,3g2,mj2");
+  assert_eq!(format.nb_streams, 2);
+  assert_eq!(format.duration, Some(Duration::from_micros(1068118)));
+  assert_eq!(format.bit_rate, Some(307823));
+  assert_eq!(format.tags["major_brand"], "isom");
+
+  let video = &info.streams[1];
+  assert_eq!(video.codec_id, ffi::AV_CODEC_ID_H264);
+  assert_eq!(video.codec_name.as_deref(), Some("h264"));
+  assert_eq!(video.codec_type.as_deref(), Some("video"));
+  assert_eq!(video.codec_tag_string, "avc1");
+  let details = video.video.as_ref().unwrap();
+  assert_eq!((details.width, details.height), (320, 180));
+  assert_eq!(details.pix_fmt.as_deref(), Some("yuv420p"));
+  assert!(video.audio.is_none());
+
+  let audio = &info.streams[0];
+  assert_eq!(audio.codec_name.as_deref(), Some("aac"));
+  assert_eq!(audio.profile.as_deref(), Some("LC"));
+  let details = audio.audio.as_ref().unwrap();
+  assert_eq!(details.sample_fmt.as_deref(), Some("fltp"));
+  assert_eq!(details.channels, 2);
+  assert_eq!(details.channel_layout.as_deref(), Some("stereo"));
+  assert!(audio.video.is_none());
+
+  assert!(info.chapters.is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn media_info_test1() {
+  let info = MediaInfo::probe(cstr!("tests/assets/vids/bear.mp4")).unwrap();
+  let json = serde_json::to_value(&info).unwrap();
+
+  let format = &json["format"];
+  assert_eq!(format["duration"], "1.068118");
+  assert_eq!(format["bit_rate"], "307823");
+  assert_eq!(format["nb_streams"], 2);
+  assert_eq!(format["tags"]["major_brand"], "isom");
+
+  let video = &json["streams"][1];
+  assert_eq!(video["index"], 1);
+  assert_eq!(video["codec_type"], "video");
+  assert_eq!(video["codec_tag"], "0x31637661");
+  assert_eq!(video["width"], 320);
+  assert_eq!(video["r_frame_rate"], "30000/1001");
+  assert_eq!(video["disposition"]["default"], 1);
+  assert_eq!(video["disposition"]["attached_pic"], 0);
+  assert!(video.get("sample_rate").is_none());
+
+  let audio = &json["streams"][0];
+  assert_eq!(audio["sample_rate"], "44100");
+  assert_eq!(audio["channel_layout"], "stereo");
+  assert!(audio.get("width").is_none());
+}
@@ -0,0 +1,100 @@
+//! Build a multi-program transport stream, then demux a single program of it.
+use anyhow::{Context, Result};
+use cstr::cstr;
+use rs_ffmpeg::{
+  avformat::{AVFormatContextInput, AVFormatContextOutput},
+  avutil::AVDictionary,
+  ffi,
+};
+use std::ffi::{CStr, CString};
+
+/// Mux every stream of `input_path` into its own program of an MPEG-TS file.
+fn build_mpts(input_path: &CStr, output_path: &CStr) -> Result<()> {
+  let mut input_format_context =
+    AVFormatContextInput::open(input_path, None, &mut None)
+      .context("Create input format context failed.")?;
+  let mut output_format_context =
+    AVFormatContextOutput::create(output_path, None)
+      .context("Create output format context failed.")?;
+
+  for (index, stream) in input_format_context.streams().iter().enumerate() {
+    output_format_context
+      .new_stream()
+      .set_codecpar(stream.codecpar().clone());
+    let program_id = index as i32 + 1;
+    output_format_context
+      .new_program(program_id)
+      .set_metadata(Some(AVDictionary::new(
+        cstr!("service_name"),
+        &CString::new(format!("Channel {program_id}"))?,
+        0,
+      )));
+    output_format_context.add_stream_to_program(program_id, index);
+  }
+
+  output_format_context
+    .write_header(&mut None)
+    .context("Write header failed.")?;
+  while let Some(mut packet) = input_format_context
+    .read_packet()
+    .context("Read packet failed.")?
+  {
+    let stream_index = packet.stream_index as usize;
+    packet.rescale_ts(
+      input_format_context.streams()[stream_index].time_base,
+      output_format_context.streams()[stream_index].time_base,
+    );
+    packet.set_pos(-1);
+    output_format_context
+      .interleaved_write_frame(&mut packet)
+      .context("Interleaved write frame failed.")?;
+  }
+  output_format_context
+    .write_trailer()
+    .context("Write trailer failed.")
+}
+
+#[test]
+fn programs_test0() {
+  std::fs::create_dir_all("tests/output/programs/").unwrap();
+  let output_path = cstr!("tests/output/programs/bear.ts");
+  build_mpts(cstr!("tests/assets/vids/bear.mp4"), output_path).unwrap();
+
+  let mut input_format_context =
+    AVFormatContextInput::open(output_path, None, &mut None).unwrap();
+  let programs = input_format_context.programs();
+  assert_eq!(programs.len(), 2);
+  for program in programs {
+    assert_eq!(program.stream_indexes().len(), 1);
+    assert!(program.pmt_pid > 0);
+    assert!(program
+      .metadata()
+      .unwrap()
+      .get(cstr!("service_name"), None, 0)
+      .is_some());
+  }
+  let first_stream = programs[0].stream_indexes()[0] as usize;
+  assert_eq!(
+    input_format_context
+      .find_programs_from_stream(first_stream)
+      .next()
+      .unwrap()
+      .id,
+    programs[0].id
+  );
+
+  // Only demux the first program.
+  for program in &mut input_format_context.programs_mut()[1..] {
+    program.set_discard(ffi::AVDISCARD_ALL);
+  }
+  for (index, stream) in
+    input_format_context.streams_mut().iter_mut().enumerate()
+  {
+    if index != first_stream {
+      stream.set_discard(ffi::AVDISCARD_ALL);
+    }
+  }
+  while let Some(packet) = input_format_context.read_packet().unwrap() {
+    assert_eq!(packet.stream_index as usize, first_stream);
+  }
+}
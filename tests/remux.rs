@@ -4,10 +4,10 @@ use cstr::cstr;
 use rs_ffmpeg::{
   avcodec::AVPacket,
   avformat::{AVFormatContextInput, AVFormatContextOutput},
-  avutil::{ts2str, ts2timestr},
+  avutil::{ts2str, ts2timestr, AVDictionary},
   ffi::AVRational,
 };
-use std::ffi::CStr;
+use std::{ffi::CStr, time::Duration};
 
 fn log_packet(time_base: AVRational, pkt: &AVPacket, tag: &str) {
   println!(
@@ -55,6 +55,12 @@ fn remux(input_path: &CStr, output_path: &CStr) -> Result<()> {
       })
       .collect()
   };
+  for chapter in input_format_context.chapters() {
+    let metadata = chapter.metadata().map(|x| AVDictionary::clone(&x));
+    output_format_context
+      .add_chapter(chapter.id, chapter.time_base, chapter.start, chapter.end)
+      .set_metadata(metadata);
+  }
   output_format_context
     .dump(0, output_path)
     .context("Dump output format context failed.")?;
@@ -99,3 +105,68 @@ fn remux_test0() {
   )
   .unwrap();
 }
+
+/// Remux MP4 to MKV with authored chapters, then remux the MKV again and check
+/// the chapters survive.
+#[test]
+fn remux_test1() {
+  std::fs::create_dir_all("tests/output/remux/").unwrap();
+  let input_path = cstr!("tests/assets/vids/bear.mp4");
+  let chapters_path = cstr!("tests/output/remux/bear_chapters.mkv");
+
+  {
+    let mut input_format_context =
+      AVFormatContextInput::open(input_path, None, &mut None).unwrap();
+    let mut output_format_context =
+      AVFormatContextOutput::create(chapters_path, None).unwrap();
+    for stream in input_format_context.streams() {
+      let mut out_stream = output_format_context.new_stream();
+      out_stream.set_codecpar(stream.codecpar().clone());
+      // The tag of the input container may be invalid in the output one.
+      out_stream.codecpar_mut().set_codec_tag(0);
+    }
+    let time_base = AVRational { num: 1, den: 1000 };
+    for (id, start, end, title) in
+      [(1, 0, 500, cstr!("Intro")), (2, 500, 1000, cstr!("Outro"))]
+    {
+      output_format_context
+        .add_chapter(id, time_base, start, end)
+        .set_metadata(Some(AVDictionary::new(cstr!("title"), title, 0)));
+    }
+    assert_eq!(output_format_context.chapters().len(), 2);
+
+    output_format_context.write_header(&mut None).unwrap();
+    while let Some(mut packet) = input_format_context.read_packet().unwrap() {
+      let stream_index = packet.stream_index as usize;
+      packet.rescale_ts(
+        input_format_context.streams()[stream_index].time_base,
+        output_format_context.streams()[stream_index].time_base,
+      );
+      packet.set_pos(-1);
+      output_format_context
+        .interleaved_write_frame(&mut packet)
+        .unwrap();
+    }
+    output_format_context.write_trailer().unwrap();
+  }
+
+  let remuxed_path = cstr!("tests/output/remux/bear_chapters_remuxed.mkv");
+  remux(chapters_path, remuxed_path).unwrap();
+
+  let input_format_context =
+    AVFormatContextInput::open(remuxed_path, None, &mut None).unwrap();
+  let chapters = input_format_context.chapters();
+  assert_eq!(chapters.len(), 2);
+  assert_eq!(chapters[0].start_time(), Duration::ZERO);
+  assert_eq!(chapters[0].end_time(), Duration::from_millis(500));
+  assert_eq!(chapters[1].start_time(), Duration::from_millis(500));
+  assert_eq!(
+    chapters[1]
+      .metadata()
+      .unwrap()
+      .get(cstr!("title"), None, 0)
+      .unwrap()
+      .value(),
+    cstr!("Outro")
+  );
+}
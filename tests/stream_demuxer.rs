@@ -0,0 +1,78 @@
+use cstr::cstr;
+use rs_ffmpeg::{avformat::AVFormatContextInput, ffi, tools::StreamDemuxer};
+use std::{ffi::CStr, thread};
+
+fn open(path: &CStr) -> AVFormatContextInput {
+  AVFormatContextInput::open(path, None, &mut None).unwrap()
+}
+
+/// Count packets of each stream by plain sequential reading.
+fn count_packets(path: &CStr) -> Vec<usize> {
+  let mut input_format_context = open(path);
+  let mut counts = vec![0; input_format_context.streams().len()];
+  for packet in input_format_context.packets() {
+    counts[packet.unwrap().stream_index as usize] += 1;
+  }
+  counts
+}
+
+/// Consume video and audio on two threads with tiny queues.
+#[test]
+fn stream_demuxer_test0() {
+  let path = cstr!("tests/assets/vids/bear.mp4");
+  let expected = count_packets(path);
+
+  let input_format_context = open(path);
+  let (video_index, _) = input_format_context
+    .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+    .unwrap()
+    .unwrap();
+  let (audio_index, _) = input_format_context
+    .find_best_stream(ffi::AVMEDIA_TYPE_AUDIO)
+    .unwrap()
+    .unwrap();
+
+  let demuxer = StreamDemuxer::new(input_format_context, 2);
+  let handles: Vec<_> = [video_index, audio_index]
+    .into_iter()
+    .map(|index| {
+      let receiver = demuxer.receiver(index).unwrap();
+      assert!(demuxer.receiver(index).is_none());
+      thread::spawn(move || {
+        let mut last_dts = i64::MIN;
+        let mut count = 0;
+        for packet in receiver {
+          let packet = packet.unwrap();
+          assert_eq!(packet.stream_index as usize, index);
+          assert!(packet.dts >= last_dts);
+          last_dts = packet.dts;
+          count += 1;
+        }
+        count
+      })
+    })
+    .collect();
+  let counts: Vec<_> = handles.into_iter().map(|x| x.join().unwrap()).collect();
+
+  assert_eq!(counts, [expected[video_index], expected[audio_index]]);
+}
+
+/// A dropped receiver doesn't block the others.
+#[test]
+fn stream_demuxer_test1() {
+  let path = cstr!("tests/assets/vids/bear.mp4");
+  let expected = count_packets(path);
+
+  let input_format_context = open(path);
+  let (video_index, _) = input_format_context
+    .find_best_stream(ffi::AVMEDIA_TYPE_VIDEO)
+    .unwrap()
+    .unwrap();
+  let demuxer = StreamDemuxer::new(input_format_context, 1);
+  let receiver = demuxer.receiver(video_index).unwrap();
+  for index in 0..expected.len() {
+    drop(demuxer.receiver(index));
+  }
+
+  assert_eq!(receiver.count(), expected[video_index]);
+}
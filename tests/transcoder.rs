@@ -0,0 +1,116 @@
+//! Transcode with per-stream policies using `Transcoder`.
+use cstr::cstr;
+use rs_ffmpeg::{
+  avformat::AVFormatContextInput,
+  ffi,
+  tools::{EncoderConfig, StreamPolicy, Transcoder},
+};
+use std::{
+  ffi::CStr,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+fn best_stream(path: &CStr, media_type: ffi::AVMediaType) -> usize {
+  AVFormatContextInput::open(path, None, &mut None)
+    .unwrap()
+    .find_best_stream(media_type)
+    .unwrap()
+    .unwrap()
+    .0
+}
+
+/// Re-encode and scale the video, copy the audio.
+#[test]
+fn transcoder_test0() {
+  std::fs::create_dir_all("tests/output/transcoder/").unwrap();
+  let input = cstr!("tests/assets/vids/bear.mp4");
+  let output = cstr!("tests/output/transcoder/bear_small.mkv");
+  let video_index = best_stream(input, ffi::AVMEDIA_TYPE_VIDEO);
+  let audio_index = best_stream(input, ffi::AVMEDIA_TYPE_AUDIO);
+
+  let mut transcoder = Transcoder::new(input, output).unwrap();
+  transcoder
+    .set_policy(
+      video_index,
+      StreamPolicy::Reencode(EncoderConfig {
+        codec_id: Some(ffi::AV_CODEC_ID_MPEG4),
+        ..Default::default()
+      }),
+    )
+    .unwrap();
+  transcoder
+    .set_filter(video_index, Some(cstr!("scale=160:-2")))
+    .unwrap();
+  let progress = Arc::new(Mutex::new(vec![]));
+  {
+    let progress = progress.clone();
+    transcoder.set_progress_callback(move |x| {
+      progress.lock().unwrap().push(*x);
+    });
+  }
+  let input_codec_id = transcoder.input().streams()[audio_index]
+    .codecpar()
+    .codec_id;
+  transcoder.run().unwrap();
+
+  let progress = progress.lock().unwrap();
+  let last = progress.last().unwrap();
+  let duration = last.duration.unwrap();
+  assert!(last.position <= duration);
+  assert!(last.position + Duration::from_millis(500) > duration);
+  assert!(last.frames > 0);
+  assert!(last.packets > last.frames);
+  assert!(progress.windows(2).all(|x| x[0].packets <= x[1].packets));
+
+  let output = AVFormatContextInput::open(output, None, &mut None).unwrap();
+  let streams = output.streams();
+  assert_eq!(streams.len(), 2);
+  let video = streams
+    .iter()
+    .find(|x| x.codecpar().codec_type().is_video())
+    .unwrap();
+  assert_eq!(video.codecpar().codec_id, ffi::AV_CODEC_ID_MPEG4);
+  assert_eq!(video.codecpar().width, 160);
+  let audio = streams
+    .iter()
+    .find(|x| x.codecpar().codec_type().is_audio())
+    .unwrap();
+  assert_eq!(audio.codecpar().codec_id, input_codec_id);
+}
+
+/// Re-encode the filtered audio to AAC, which needs fixed size frames, and
+/// drop the video.
+#[test]
+fn transcoder_test1() {
+  std::fs::create_dir_all("tests/output/transcoder/").unwrap();
+  let input = cstr!("tests/assets/vids/bear.mp4");
+  let output = cstr!("tests/output/transcoder/bear_quiet.m4a");
+  let video_index = best_stream(input, ffi::AVMEDIA_TYPE_VIDEO);
+  let audio_index = best_stream(input, ffi::AVMEDIA_TYPE_AUDIO);
+
+  let mut transcoder = Transcoder::new(input, output).unwrap();
+  transcoder
+    .set_policy(video_index, StreamPolicy::Drop)
+    .unwrap();
+  transcoder
+    .set_policy(
+      audio_index,
+      StreamPolicy::Reencode(EncoderConfig {
+        codec_id: Some(ffi::AV_CODEC_ID_AAC),
+        bit_rate: Some(64000),
+        ..Default::default()
+      }),
+    )
+    .unwrap();
+  transcoder
+    .set_filter(audio_index, Some(cstr!("volume=0.5")))
+    .unwrap();
+  transcoder.run().unwrap();
+
+  let output = AVFormatContextInput::open(output, None, &mut None).unwrap();
+  assert_eq!(output.streams().len(), 1);
+  let codecpar = output.streams()[0].codecpar();
+  assert_eq!(codecpar.codec_id, ffi::AV_CODEC_ID_AAC);
+  assert!(output.duration > 0);
+}